@@ -0,0 +1,229 @@
+// An RGB image to draw on, and write out as a PBM or a PNG
+//
+// Nothing is compressed: the PNG's image data is zlib "stored" blocks. An image that is all black
+// and white is written one bit to a pixel, which keeps big sparse pictures (like the exact cover
+// matrix) small enough anyway.
+//
+
+use font;
+
+pub type Color = [u8; 3];
+
+pub const BLACK: Color = [0, 0, 0];
+pub const WHITE: Color = [255, 255, 255];
+
+#[derive(Debug, Clone)]
+pub struct Bitmap {
+    width: usize,
+    height: usize,
+    pixels: Vec<Color>,
+}
+
+impl Bitmap {
+    pub fn new(width: usize, height: usize, background: Color) -> Self {
+        Bitmap {
+            width,
+            height,
+            pixels: vec![background; width * height],
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> Color {
+        self.pixels[y * self.width + x]
+    }
+
+    // Pixels off the edge are ignored
+    pub fn set(&mut self, x: usize, y: usize, color: Color) {
+        if x < self.width && y < self.height {
+            self.pixels[y * self.width + x] = color;
+        }
+    }
+
+    pub fn fill_rect(&mut self, x: usize, y: usize, w: usize, h: usize, color: Color) {
+        for y in y..(y + h).min(self.height) {
+            for x in x..(x + w).min(self.width) {
+                self.set(x, y, color);
+            }
+        }
+    }
+
+    // The outline of a rectangle, `thickness` pixels wide, inside it
+    pub fn frame_rect(
+        &mut self,
+        x: usize,
+        y: usize,
+        w: usize,
+        h: usize,
+        thickness: usize,
+        color: Color,
+    ) {
+        let t = thickness.min(w).min(h);
+        self.fill_rect(x, y, w, t, color);
+        self.fill_rect(x, y + h - t, w, t, color);
+        self.fill_rect(x, y, t, h, color);
+        self.fill_rect(x + w - t, y, t, h, color);
+    }
+
+    // How much room `text` takes along its line, at `scale` pixels to a font pixel
+    pub fn text_width(text: &str, scale: usize) -> usize {
+        let n = text.chars().count();
+        (n * (font::WIDTH + 1)).saturating_sub(1) * scale
+    }
+
+    pub fn text_height(scale: usize) -> usize {
+        font::HEIGHT * scale
+    }
+
+    // Write `text` left to right, with its top left corner at `x`, `y`
+    pub fn draw_text(&mut self, x: usize, y: usize, text: &str, scale: usize, color: Color) {
+        for (i, ch) in text.chars().enumerate() {
+            let left = x + i * (font::WIDTH + 1) * scale;
+            for (dy, bits) in font::glyph(ch).iter().enumerate() {
+                for dx in 0..font::WIDTH {
+                    if bits & 1 << (font::WIDTH - 1 - dx) != 0 {
+                        self.fill_rect(left + dx * scale, y + dy * scale, scale, scale, color);
+                    }
+                }
+            }
+        }
+    }
+
+    // Write `text` bottom to top (turned a quarter left), with its bottom left corner at `x`, `y`
+    pub fn draw_text_up(&mut self, x: usize, y: usize, text: &str, scale: usize, color: Color) {
+        for (i, ch) in text.chars().enumerate() {
+            let bottom = y - i * (font::WIDTH + 1) * scale;
+            for (dy, bits) in font::glyph(ch).iter().enumerate() {
+                for dx in 0..font::WIDTH {
+                    if bits & 1 << (font::WIDTH - 1 - dx) != 0 {
+                        let px = x + dy * scale;
+                        let py = bottom - (dx + 1) * scale;
+                        self.fill_rect(px, py, scale, scale, color);
+                    }
+                }
+            }
+        }
+    }
+
+    fn is_black_and_white(&self) -> bool {
+        self.pixels.iter().all(|p| *p == BLACK || *p == WHITE)
+    }
+
+    fn is_dark(color: Color) -> bool {
+        let [r, g, b] = color;
+        let luma = 299 * r as usize + 587 * g as usize + 114 * b as usize;
+        luma < 128 * 1000
+    }
+
+    // Rows of one bit pixels, set where `one_bit` says, each row padded to a whole byte
+    fn packed_rows(&self, one_bit: impl Fn(Color) -> bool) -> Vec<Vec<u8>> {
+        (0..self.height)
+            .map(|y| {
+                let mut row = vec![0; self.width.div_ceil(8)];
+                for x in 0..self.width {
+                    if one_bit(self.get(x, y)) {
+                        row[x / 8] |= 0x80 >> (x % 8);
+                    }
+                }
+                row
+            })
+            .collect()
+    }
+
+    // As a binary PBM (P4). Dark pixels are black, and everything else is white.
+    pub fn to_pbm(&self) -> Vec<u8> {
+        let mut out = format!("P4\n{} {}\n", self.width, self.height).into_bytes();
+        for row in self.packed_rows(Bitmap::is_dark) {
+            out.extend(row);
+        }
+        out
+    }
+
+    pub fn to_png(&self) -> Vec<u8> {
+        let (depth, kind, rows) = if self.is_black_and_white() {
+            (1, 0, self.packed_rows(|p| p == WHITE))
+        } else {
+            let rows = (0..self.height)
+                .map(|y| (0..self.width).flat_map(|x| self.get(x, y)).collect())
+                .collect();
+            (8, 2, rows)
+        };
+
+        let mut header = vec![];
+        header.extend(&(self.width as u32).to_be_bytes());
+        header.extend(&(self.height as u32).to_be_bytes());
+        header.extend(&[depth, kind, 0, 0, 0]);
+
+        // Every row starts with its filter type, which is always 0 (none)
+        let mut data = vec![];
+        for row in rows {
+            data.push(0);
+            data.extend(row);
+        }
+
+        let mut png = vec![0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A];
+        png_chunk(&mut png, b"IHDR", &header);
+        png_chunk(&mut png, b"IDAT", &zlib_stored(&data));
+        png_chunk(&mut png, b"IEND", &[]);
+        png
+    }
+}
+
+fn png_chunk(png: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    png.extend(&(data.len() as u32).to_be_bytes());
+    let start = png.len();
+    png.extend(kind);
+    png.extend(data);
+    let crc = crc32(&png[start..]);
+    png.extend(&crc.to_be_bytes());
+}
+
+// `data` in a zlib stream of uncompressed deflate blocks
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    let mut out = vec![0x78, 0x01];
+    let mut blocks = data.chunks(0xFFFF).peekable();
+    if blocks.peek().is_none() {
+        out.extend(&[1, 0, 0, 0xFF, 0xFF]);
+    }
+    while let Some(block) = blocks.next() {
+        let last = blocks.peek().is_none() as u8;
+        let len = block.len() as u16;
+        out.push(last);
+        out.extend(&len.to_le_bytes());
+        out.extend(&(!len).to_le_bytes());
+        out.extend(block);
+    }
+    out.extend(&adler32(data).to_be_bytes());
+    out
+}
+
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for byte in bytes {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0xEDB8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+fn adler32(bytes: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for byte in bytes {
+        a = (a + *byte as u32) % 65521;
+        b = (b + a) % 65521;
+    }
+    b << 16 | a
+}
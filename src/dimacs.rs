@@ -0,0 +1,155 @@
+// Sudoku as SAT, in the DIMACS CNF format that SAT solvers read
+//
+// There is a variable for every `[r, c, n]`:
+//
+//     ((r - 1) * width + (c - 1)) * symbols + n
+//
+// which for a regular sudoku is the usual `81 * (r - 1) + 9 * (c - 1) + n`. Each column of the web
+// becomes a one-hot constraint over the rows it contains: at least one of them (for primary
+// columns: cells, rows, columns, and boxes) and no two of them (for every column). Clues are unit
+// clauses, as are the candidates a cell may not hold.
+//
+// Rules (like thermometers and Kropki dots) aren't encoded, but `check_solution` checks a decoded
+// grid against them along with everything else.
+//
+
+use rules::Board;
+use SudokuWeb;
+
+impl SudokuWeb {
+    fn variable(&self, r: usize, c: usize, n: usize) -> usize {
+        self.possibility_index(r, c, n) + 1
+    }
+
+    // The puzzle (with `prop_solution` as its clues, and `prop_candidates` as its candidates) as
+    // DIMACS CNF
+    pub fn to_dimacs(&self) -> String {
+        let mut clauses: Vec<Vec<isize>> = vec![];
+
+        for (i, rows) in self.rows_of_columns().iter().enumerate() {
+            let literals: Vec<isize> = rows
+                .iter()
+                .map(|[r, c, n]| self.variable(*r, *c, *n) as isize)
+                .collect();
+            if i < self.constraints() {
+                clauses.push(literals.clone());
+            }
+            for (j, a) in literals.iter().enumerate() {
+                for b in literals[j + 1..].iter() {
+                    clauses.push(vec![-a, -b]);
+                }
+            }
+        }
+
+        let candidates = self.prop_candidates.iter().chain(std::iter::repeat(&!0));
+        for ([r, c], mask) in self.cells().into_iter().zip(candidates) {
+            for n in 1..=self.symbols() {
+                if !self.allows(r, c, n) || mask & 1 << (n - 1) == 0 {
+                    clauses.push(vec![-(self.variable(r, c, n) as isize)]);
+                }
+            }
+        }
+
+        for [r, c, n] in self.prop_solution.iter() {
+            clauses.push(vec![self.variable(*r, *c, *n) as isize]);
+        }
+
+        let mut cnf = format!(
+            "c sudoku: {} belts and {} curtains of boxes, {} symbols\n",
+            self.belts,
+            self.curtains,
+            self.symbols()
+        );
+        for [ro, co] in self.grids.iter() {
+            cnf += &format!("c grid offset by {} rows and {} columns\n", ro, co);
+        }
+        cnf += &format!("p cnf {} {}\n", self.possibilities(), clauses.len());
+        for clause in clauses {
+            for literal in clause {
+                cnf += &format!("{} ", literal);
+            }
+            cnf += "0\n";
+        }
+        cnf
+    }
+
+    // Read a SAT solver's output for `to_dimacs` back into a full grid, and check it. Both the
+    // competition format (`s SATISFIABLE`, then `v` lines of literals) and MiniSat's (`SAT`, then a
+    // line of literals) are understood.
+    pub fn read_model(&self, model: &str) -> Result<Vec<[usize; 3]>, String> {
+        let mut literals: Vec<isize> = vec![];
+        for line in model.lines() {
+            let words: Vec<&str> = line.split_whitespace().collect();
+            match words.as_slice() {
+                [] | ["c", ..] | ["s", "SATISFIABLE"] | ["SAT"] => (),
+                ["s", "UNSATISFIABLE"] | ["UNSAT"] => return Err("unsatisfiable".to_string()),
+                ["v", rest @ ..] | rest => {
+                    for word in rest {
+                        let literal = word
+                            .parse()
+                            .map_err(|_| format!("not a literal: {}", word))?;
+                        literals.push(literal);
+                    }
+                }
+            }
+        }
+
+        let nums = self.symbols();
+        let mut sol = vec![];
+        for literal in literals.into_iter().filter(|l| *l > 0) {
+            let i = literal as usize - 1;
+            if i >= self.possibilities() {
+                return Err(format!("no such variable: {}", literal));
+            }
+            let (cell, n) = (i / nums, i % nums + 1);
+            sol.push([cell / self.width() + 1, cell % self.width() + 1, n]);
+        }
+        self.check_solution(&sol)?;
+        Ok(sol)
+    }
+
+    // Is `sol` a full grid that keeps every constraint of the web, its rules, its clues
+    // (`prop_solution`), and its candidates (`prop_candidates`)? If not, says what it breaks.
+    pub fn check_solution(&self, sol: &[[usize; 3]]) -> Result<(), String> {
+        let mut board = Board::new(self.height(), self.width(), self.symbols());
+        for [r, c, n] in sol.iter() {
+            if *r < 1 || *c < 1 || *r > self.height() || *c > self.width() || !self.in_grid(*r, *c)
+            {
+                return Err(format!("r{}c{} is not on the board", r, c));
+            }
+            if *n < 1 || *n > self.symbols() || !self.allows(*r, *c, *n) {
+                return Err(format!("r{}c{} can't hold {}", r, c, n));
+            }
+            board.set(*r, *c, *n);
+        }
+
+        for (i, rows) in self.rows_of_columns().iter().enumerate() {
+            let count = rows.iter().filter(|id| sol.contains(id)).count();
+            if count > 1 || (count == 0 && i < self.constraints()) {
+                return Err(if i < self.constraints() {
+                    format!("column {:?} is filled {} times", self.column_keys[i], count)
+                } else {
+                    format!("secondary column {} is filled {} times", i, count)
+                });
+            }
+        }
+
+        let candidates = self.prop_candidates.iter().chain(std::iter::repeat(&!0));
+        for ([r, c], mask) in self.cells().into_iter().zip(candidates) {
+            let n = board.get(r, c);
+            if mask & 1 << (n - 1) == 0 {
+                return Err(format!("{} is not a candidate at r{}c{}", n, r, c));
+            }
+        }
+        if let Some([r, c, n]) = self.prop_solution.iter().find(|id| !sol.contains(id)) {
+            return Err(format!("the clue r{}c{} = {} is missing", r, c, n));
+        }
+
+        for rule in self.rules.iter() {
+            if let Some(id) = sol.iter().find(|id| !rule.admits(&board, **id)) {
+                return Err(format!("r{}c{} = {} breaks a rule", id[0], id[1], id[2]));
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,552 @@
+// A dancing links engine for exact cover problems
+//
+// An exact cover problem is a sparse 0/1 matrix, and a solution is a set of its rows with exactly
+// one 1 in every primary column, and at most one 1 in every secondary column. Each row carries a
+// label of any type, to say what choosing it means: for a sudoku, an `[r, c, n]`; for N queens, a
+// square; for a pentomino tiling, a piece in some position.
+//
+// The links are stored in a slab. The first entry is `h`, the handle on the primary column
+// headers. Following `h` are all the primary column headers, then `sh`, the handle on the
+// secondary column headers, then all the secondary column headers, and then all the links.
+//
+// Because of this structure, primary column `j` (counting from 0) has its header at `j + 1`, and
+// secondary column `j` (counting on from the primary columns) at `j + 2`.
+//
+// Secondary columns hang off of `sh` instead of `h`, so `search` never chooses them and is
+// finished as soon as `h` is alone, but choosing a row still covers them, removing every other row
+// that shares one.
+//
+
+use rand::{self, Rng};
+use slab::Slab;
+
+#[derive(Debug, Clone, Copy)]
+struct Node {
+    li: usize,   // The index of the node to the left
+    ri: usize,   // ... to the right
+    ui: usize,   // ... up
+    di: usize,   // ... down
+    ci: usize,   // The index of the column header
+    size: usize, // The number of nodes in the column (only use for column headers)
+    row: usize,  // The row the node is in (only use for links)
+    i: usize,    // The index of the node, so it can tell others
+}
+
+impl Node {
+    fn new_h(i: usize) -> Self {
+        Node {
+            li: i,
+            ri: i,
+            ui: i,
+            di: i,
+            ci: i,
+            i,
+            size: 0,
+            row: 0,
+        }
+    }
+
+    // `hi`: The index of the handle on the list this header joins
+    fn new_column_header(i: usize, hi: usize) -> Self {
+        Node {
+            li: i - 1,
+            ri: hi,
+            ui: i,
+            di: i,
+            ci: i,
+            i,
+            size: 0,
+            row: 0,
+        }
+    }
+
+    fn new_link(
+        li: usize,
+        ri: usize,
+        ui: usize,
+        di: usize,
+        ci: usize,
+        i: usize,
+        row: usize,
+    ) -> Self {
+        Node {
+            li,
+            ri,
+            ui,
+            di,
+            ci,
+            i,
+            row,
+            size: 0,
+        }
+    }
+}
+
+// What a `search` consults as it goes. Everything but `found` can be left alone.
+pub trait Hook<L> {
+    // May `row` be chosen next?
+    fn admits(&mut self, _cover: &ExactCover<L>, _row: usize) -> bool {
+        true
+    }
+
+    // The search is about to go deeper. Returns false to give up, which unwinds the search as if
+    // `found` had said to stop.
+    fn proceed(&mut self, _cover: &ExactCover<L>) -> bool {
+        true
+    }
+
+    // `column`, which has `size` rows left, is the one to choose a row from next
+    fn column(&mut self, _cover: &ExactCover<L>, _column: usize, _size: usize) {}
+
+    // `row` has just been chosen (and its columns covered). Returns the rows to remove from the
+    // matrix until it is unchosen again.
+    fn chosen(&mut self, _cover: &ExactCover<L>, _row: usize) -> Vec<usize> {
+        vec![]
+    }
+
+    // `row` has been unchosen, and its columns uncovered
+    fn unchosen(&mut self, _cover: &ExactCover<L>, _row: usize) {}
+
+    // `rows` (in the order they were chosen) are a solution. Returns whether to keep looking.
+    fn found(&mut self, cover: &ExactCover<L>, rows: &[usize]) -> bool;
+}
+
+// The hook for `solutions`
+struct Collect<L> {
+    solutions: Vec<Vec<L>>,
+    limit: usize,
+}
+
+impl<L: Clone> Hook<L> for Collect<L> {
+    fn found(&mut self, cover: &ExactCover<L>, rows: &[usize]) -> bool {
+        let labels = rows.iter().map(|row| cover.label(*row).clone()).collect();
+        self.solutions.push(labels);
+        self.solutions.len() < self.limit
+    }
+}
+
+#[derive(Debug)]
+pub struct ExactCover<L> {
+    slab: Slab<Node>,
+    sh: usize,
+    primary: usize,
+    secondary: usize,
+    labels: Vec<L>,
+    firsts: Vec<usize>,
+}
+
+impl<L> ExactCover<L> {
+    // An empty matrix with `primary` primary columns and `secondary` secondary columns
+    pub fn new(primary: usize, secondary: usize) -> Self {
+        let mut cover = ExactCover {
+            slab: Slab::with_capacity(primary + secondary + 2),
+            sh: 0,
+            primary,
+            secondary,
+            labels: vec![],
+            firsts: vec![],
+        };
+
+        // h is used as a reference to the list of headers. It also guarantees that the list of
+        // headers will be circular, even when all headers have been removed. It is always the
+        // first element in the slab.
+        cover.slab.insert(Node::new_h(0));
+        for n in 1..=primary {
+            let key = cover.slab.insert(Node::new_column_header(n, 0));
+            cover.set_new_links(key);
+        }
+
+        // sh does for the secondary headers what h does for the primary ones
+        cover.sh = cover.slab.insert(Node::new_h(primary + 1));
+        for n in 0..secondary {
+            let key = cover
+                .slab
+                .insert(Node::new_column_header(cover.sh + 1 + n, cover.sh));
+            cover.set_new_links(key);
+        }
+        cover
+    }
+
+    // Make room for this many more 1s
+    pub fn reserve(&mut self, ones: usize) {
+        self.slab.reserve_exact(ones);
+    }
+
+    pub fn columns(&self) -> usize {
+        self.primary + self.secondary
+    }
+
+    pub fn rows(&self) -> usize {
+        self.labels.len()
+    }
+
+    pub fn label(&self, row: usize) -> &L {
+        &self.labels[row]
+    }
+
+    // Add a row with a 1 in each of `columns` (primary columns first, then secondary columns, all
+    // counting from 0), and return its index. The row is covered by the first column it lists.
+    //
+    // Panics unless `columns` lists at least one column, and each of them only once.
+    //
+    pub fn add_row(&mut self, label: L, columns: &[usize]) -> usize {
+        assert!(!columns.is_empty(), "a row needs a 1 in some column");
+        for (j, column) in columns.iter().enumerate() {
+            assert!(
+                *column < self.columns(),
+                "there is no column {} of {}",
+                column,
+                self.columns()
+            );
+            assert!(
+                !columns[..j].contains(column),
+                "column {} is listed twice",
+                column
+            );
+        }
+
+        let row = self.labels.len();
+        self.labels.push(label);
+        self.firsts.push(0);
+
+        for (j, column) in columns.iter().enumerate() {
+            let ci = self.header(*column);
+            let ui = self.slab[ci].ui;
+            let key = self.slab.vacant_entry().key();
+            let (li, ri) = match j {
+                0 => (key, key),
+                _ => (self.slab[self.firsts[row]].li, self.firsts[row]),
+            };
+            self.slab
+                .insert(Node::new_link(li, ri, ui, ci, ci, key, row));
+            self.set_new_links(key);
+
+            if j == 0 {
+                self.firsts[row] = key;
+            }
+        }
+        row
+    }
+
+    fn header(&self, column: usize) -> usize {
+        if column < self.primary {
+            column + 1
+        } else {
+            column + 2
+        }
+    }
+
+    // The column with its header at `ci` (the opposite of `header`)
+    fn column_of(&self, ci: usize) -> usize {
+        if ci <= self.primary {
+            ci - 1
+        } else {
+            ci - 2
+        }
+    }
+
+    // Given the key to a freshly created node, make sure it's neighbors point to it
+    fn set_new_links(&mut self, new_key: usize) {
+        let node_is = self.at(new_key);
+
+        self.slab[node_is.li].ri = new_key;
+        self.slab[node_is.ri].li = new_key;
+        self.slab[node_is.ui].di = new_key;
+        self.slab[node_is.di].ui = new_key;
+
+        if new_key != node_is.ci {
+            self.slab[node_is.ci].size += 1;
+        }
+    }
+
+    fn at(&self, i: usize) -> Node {
+        self.slab[i]
+    }
+
+    // Is `row` still in the matrix? Rows can be removed (see `remove_row`), or covered by some
+    // other row's column (as in `select`).
+    //
+    // When a column is covered, all of its rows are unlinked from every other column. So if a
+    // row's first node is linked, then either the whole row is, or that first node's own column
+    // is covered.
+    //
+    pub fn is_live(&self, row: usize) -> bool {
+        let key = self.firsts[row];
+        key != 0
+            && self.slab[self.slab[key].ui].di == key
+            && !self.is_covered_header(self.at(key).ci)
+    }
+
+    // Is the column with this header covered? Checks that it is still connected to its neighbor
+    // (which works for primary and secondary columns alike).
+    fn is_covered_header(&self, ci: usize) -> bool {
+        self.slab[self.slab[ci].li].ri != ci
+    }
+
+    pub fn is_covered(&self, column: usize) -> bool {
+        self.is_covered_header(self.header(column))
+    }
+
+    // The rows still in `column`
+    pub fn rows_in(&self, column: usize) -> Vec<usize> {
+        let c = self.at(self.header(column));
+        let mut rows = vec![];
+        let mut j = self.at(c.di);
+        while j.i != c.i {
+            rows.push(j.row);
+            j = self.at(j.di);
+        }
+        rows
+    }
+
+    // Take `row` out of the matrix, until `replace_row` puts it back. Removals must be undone in
+    // the opposite order.
+    pub fn remove_row(&mut self, row: usize) {
+        let first = self.at(self.firsts[row]);
+        let mut j = first;
+        loop {
+            self.slab[j.di].ui = j.ui;
+            self.slab[j.ui].di = j.di;
+            self.slab[j.ci].size -= 1;
+
+            j = self.at(j.ri);
+            if j.i == first.i {
+                break;
+            }
+        }
+    }
+
+    pub fn replace_row(&mut self, row: usize) {
+        let first = self.at(self.firsts[row]);
+        let mut j = self.at(first.li);
+        loop {
+            self.slab[j.di].ui = j.i;
+            self.slab[j.ui].di = j.i;
+            self.slab[j.ci].size += 1;
+
+            if j.i == first.i {
+                break;
+            }
+            j = self.at(j.li);
+        }
+    }
+
+    // Choose `row` before searching, covering all of its columns. Returns false (and changes
+    // nothing) if the row isn't live, or one of its columns is already covered. Must be undone by
+    // `deselect`, in the opposite order.
+    pub fn select(&mut self, row: usize) -> bool {
+        if !self.is_live(row) {
+            return false;
+        }
+        let first = self.at(self.firsts[row]);
+        let mut j = first;
+        loop {
+            if self.is_covered_header(j.ci) {
+                while j.i != first.i {
+                    j = self.at(j.li);
+                    let c = self.at(j.ci);
+                    self.uncover_column(&c);
+                }
+                return false;
+            }
+            let c = self.at(j.ci);
+            self.cover_column(&c);
+
+            j = self.at(j.ri);
+            if j.i == first.i {
+                return true;
+            }
+        }
+    }
+
+    pub fn deselect(&mut self, row: usize) {
+        let first = self.at(self.firsts[row]);
+        let mut j = self.at(first.li);
+        loop {
+            let c = self.at(j.ci);
+            self.uncover_column(&c);
+
+            if j.i == first.i {
+                break;
+            }
+            j = self.at(j.li);
+        }
+    }
+
+    // Up to `limit` solutions, each as the labels of its rows
+    pub fn solutions(&mut self, limit: usize) -> Vec<Vec<L>>
+    where
+        L: Clone,
+    {
+        let mut collect = Collect {
+            solutions: vec![],
+            limit,
+        };
+        if limit > 0 {
+            self.search(false, &mut collect);
+        }
+        collect.solutions
+    }
+
+    // Search for solutions, telling `hook` about each one until it says to stop.
+    //
+    // rand: pick columns, and the order to try their rows in, randomly (good for generating
+    // puzzles, not for solving)
+    //
+    pub fn search<H: Hook<L>>(&mut self, rand: bool, hook: &mut H) {
+        let mut chosen = vec![];
+        self.dance(&mut chosen, rand, hook);
+    }
+
+    // Returns whether to keep looking
+    fn dance<H: Hook<L>>(&mut self, chosen: &mut Vec<usize>, rand: bool, hook: &mut H) -> bool {
+        if !hook.proceed(self) {
+            return false;
+        }
+        if self.at(0).ri == 0 {
+            return hook.found(self, chosen);
+        }
+
+        let c = self.choose_column(rand);
+        hook.column(self, self.column_of(c.ci), c.size);
+
+        self.cover_column(&c);
+
+        let mut rows = vec![];
+        let mut r = self.at(c.di);
+        while r.i != c.i {
+            rows.push(r.i);
+            r = self.at(r.di);
+        }
+        if rand {
+            rand::thread_rng().shuffle(&mut rows);
+        }
+
+        let mut going = true;
+        for ri in rows {
+            let r = self.at(ri);
+            if !going {
+                break;
+            }
+            if !hook.admits(self, r.row) {
+                continue;
+            }
+            chosen.push(r.row);
+
+            let mut j = self.at(r.ri);
+            while j.i != r.i {
+                let cj = self.at(j.ci);
+                self.cover_column(&cj);
+
+                j = self.at(j.ri);
+            }
+
+            let removed = hook.chosen(self, r.row);
+            for row in removed.iter() {
+                self.remove_row(*row);
+            }
+
+            going = self.dance(chosen, rand, hook);
+
+            for row in removed.iter().rev() {
+                self.replace_row(*row);
+            }
+
+            let mut j = self.at(r.li);
+            while j.i != r.i {
+                let cj = self.at(j.ci);
+                self.uncover_column(&cj);
+
+                j = self.at(j.li);
+            }
+
+            chosen.pop();
+            hook.unchosen(self, r.row);
+        }
+
+        self.uncover_column(&c);
+        going
+    }
+
+    fn choose_column(&self, rand: bool) -> Node {
+        if rand {
+            self.choose_column_randomly()
+        } else {
+            self.choose_column_well()
+        }
+    }
+
+    fn choose_column_well(&self) -> Node {
+        let mut s = usize::MAX;
+
+        let h = self.at(0);
+        let mut j = self.at(h.ri);
+        let mut c = j;
+
+        while j.i != h.i {
+            if j.size < s {
+                s = j.size;
+                c = j;
+            }
+            j = self.at(j.ri);
+        }
+
+        c
+    }
+
+    fn choose_column_randomly(&self) -> Node {
+        let mut s = usize::MAX;
+        let h = self.at(0);
+        let mut j = self.at(h.ri);
+        let mut i: Vec<usize> = vec![];
+
+        while j.i != h.i {
+            if j.size == s {
+                i.push(j.i);
+            } else if j.size < s {
+                s = j.size;
+                i.clear();
+                i.push(j.i);
+            }
+            j = self.at(j.ri);
+        }
+        let index = rand::thread_rng().gen_range(0, i.len());
+        self.at(i[index])
+    }
+
+    fn cover_column(&mut self, c: &Node) {
+        self.slab[c.ri].li = c.li;
+        self.slab[c.li].ri = c.ri;
+
+        let mut i = self.at(c.di);
+        while i.i != c.i {
+            let mut j = self.at(i.ri);
+            while j.i != i.i {
+                self.slab[j.di].ui = j.ui;
+                self.slab[j.ui].di = j.di;
+                self.slab[j.ci].size -= 1;
+
+                j = self.at(j.ri);
+            }
+            i = self.at(i.di);
+        }
+    }
+
+    fn uncover_column(&mut self, c: &Node) {
+        let mut i = self.at(c.ui);
+        while i.i != c.i {
+            let mut j = self.at(i.li);
+            while j.i != i.i {
+                self.slab[j.di].ui = j.i;
+                self.slab[j.ui].di = j.i;
+                self.slab[j.ci].size += 1;
+
+                j = self.at(j.li);
+            }
+            i = self.at(i.ui);
+        }
+
+        self.slab[c.ri].li = c.i;
+        self.slab[c.li].ri = c.i;
+    }
+}
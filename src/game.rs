@@ -0,0 +1,618 @@
+// Playing puzzles in the terminal
+//
+// A `Game` is a puzzle (the web's `prop_solution`), its unique solution, and what the player has
+// done with it: entries, pencil marks, and the moves to undo and redo them. It knows nothing of
+// terminals; `play` draws it full screen and feeds it keys, with the terminal in raw mode (set
+// with `stty`, so nothing beyond std is needed).
+//
+// The keys:
+//
+// - arrows, or `h` `j` `k` `l`: move
+// - `1` to `9` (then `A`, `B`, ... for 10 and up): enter a symbol, or toggle its pencil mark
+// - `0`, `.`, space, or backspace: clear the cell (its entry, or else its marks)
+// - `p`: switch between entering symbols and pencil marks
+// - `u` and `r`: undo and redo
+// - `?`: a hint
+// - `c`: check the entries so far against the solution
+// - `s`: save the session (see the `session` module)
+// - `n`: a new game, as hard as this one
+// - `q`: quit (saving the session first)
+//
+
+use grading::Difficulty;
+use logic::Hint;
+use render::{color_stdout, Charset};
+use std::io::{self, IsTerminal, Read, Write};
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+use {Knowing, SudokuWeb};
+
+// Tries at `generate` for a puzzle of the difficulty asked for, before settling for the nearest
+const TRIES: usize = 50;
+
+// The most symbols a game can have, since they're entered with the keys 1-9 and then A-Z
+pub const MAX_SYMBOLS: usize = 35;
+
+// How often to look for a change in the terminal's size
+const RESIZE_EVERY: Duration = Duration::from_secs(2);
+
+const HELP: &str = "arrows move · 1-9 enter · 0 clear · p pencil · u/r undo/redo\n\
+                    ? hint · c check · s save · n new · q quit\n";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Key {
+    Up,
+    Down,
+    Left,
+    Right,
+    Backspace,
+    Char(char),
+}
+
+// The keys in what was read from a terminal in raw mode. Anything unknown is dropped.
+pub fn parse_keys(bytes: &[u8]) -> Vec<Key> {
+    let mut keys = vec![];
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            // An arrow is `ESC [ A` (or `ESC O A`, in application mode)
+            0x1b if i + 2 < bytes.len() && (bytes[i + 1] == b'[' || bytes[i + 1] == b'O') => {
+                match bytes[i + 2] {
+                    b'A' => keys.push(Key::Up),
+                    b'B' => keys.push(Key::Down),
+                    b'C' => keys.push(Key::Right),
+                    b'D' => keys.push(Key::Left),
+                    _ => {}
+                }
+                i += 2;
+            }
+            0x7f | 0x08 => keys.push(Key::Backspace),
+            // Ctrl-C
+            0x03 => keys.push(Key::Char('q')),
+            b if b.is_ascii_graphic() || b == b' ' => keys.push(Key::Char(b as char)),
+            _ => {}
+        }
+        i += 1;
+    }
+    keys
+}
+
+// What the player wants after a key
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Continue,
+    Save,
+    NewGame,
+    Quit,
+}
+
+// One change to a cell: what it held before and after, as its entry (0 for none) and its pencil
+// marks
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Move {
+    pub cell: [usize; 2],
+    pub before: (usize, u64),
+    pub after: (usize, u64),
+}
+
+#[derive(Debug)]
+pub struct Game {
+    // The puzzle is the web's `prop_solution`
+    web: SudokuWeb,
+    pub difficulty: Option<Difficulty>,
+    pub solution: Vec<[usize; 3]>,
+    pub entries: Vec<[usize; 3]>,
+    // A bit mask for each of the web's `cells()`, like `prop_candidates`
+    pub marks: Vec<u64>,
+    pub undo: Vec<Move>,
+    pub redo: Vec<Move>,
+    pub hints: usize,
+    pub cursor: [usize; 2],
+    pub pencil: bool,
+    // The time played before the clock was last started, and when that was (if it's running)
+    elapsed: Duration,
+    since: Option<Instant>,
+    // Entries found wrong by `check`, until they're changed
+    flagged: Vec<[usize; 2]>,
+    message: String,
+}
+
+impl Game {
+    // A game of the puzzle in `web.prop_solution`, which must have a unique solution
+    pub fn new(mut web: SudokuWeb) -> Result<Game, String> {
+        if web.solve(2, false, false).limit_reached() || web.uniq == Knowing::Unknown {
+            return Err("couldn't tell whether the puzzle has a unique solution".to_string());
+        }
+        if web.possible == Knowing::No {
+            return Err("the puzzle has no solution".to_string());
+        }
+        if web.uniq == Knowing::No {
+            return Err("the puzzle has more than one solution".to_string());
+        }
+        let solution = web.solution.clone();
+        let marks = vec![0; web.cells().len()];
+        let cursor = web.cells()[0];
+        Ok(Game {
+            web,
+            difficulty: None,
+            solution,
+            entries: vec![],
+            marks,
+            undo: vec![],
+            redo: vec![],
+            hints: 0,
+            cursor,
+            pencil: false,
+            elapsed: Duration::ZERO,
+            since: Some(Instant::now()),
+            flagged: vec![],
+            message: String::new(),
+        })
+    }
+
+    // A new puzzle from `generate`, as near to `difficulty` as can be found in a few tries
+    pub fn random(belts: usize, curtains: usize, difficulty: Difficulty) -> Game {
+        let mut web = SudokuWeb::new(belts, curtains);
+        let mut best = None;
+        for _ in 0..TRIES {
+            let puzzle = match web.generate() {
+                Some(puzzle) => puzzle,
+                None => continue,
+            };
+            let off = (puzzle.difficulty as isize - difficulty as isize).abs();
+            if best.as_ref().is_none_or(|(o, _)| off < *o) {
+                best = Some((off, puzzle));
+            }
+            if off == 0 {
+                break;
+            }
+        }
+        let (_, puzzle) = best.expect("no puzzle was generated");
+        web.prop_solution = puzzle.clues;
+        let mut game = Game::new(web).expect("generated puzzles are unique");
+        game.difficulty = Some(puzzle.difficulty);
+        game
+    }
+
+    pub fn web(&self) -> &SudokuWeb {
+        &self.web
+    }
+
+    // The clues, and then the entries
+    pub fn board(&self) -> Vec<[usize; 3]> {
+        let mut board = self.web.prop_solution.clone();
+        board.extend(self.entries.iter());
+        board
+    }
+
+    // What the cell at `r`, `c` holds, clue or entry, or 0
+    pub fn value(&self, r: usize, c: usize) -> usize {
+        self.web
+            .prop_solution
+            .iter()
+            .chain(self.entries.iter())
+            .find(|[er, ec, _]| [*er, *ec] == [r, c])
+            .map_or(0, |[_, _, n]| *n)
+    }
+
+    pub fn is_given(&self, r: usize, c: usize) -> bool {
+        self.web
+            .prop_solution
+            .iter()
+            .any(|[gr, gc, _]| [*gr, *gc] == [r, c])
+    }
+
+    fn mark_index(&self, [r, c]: [usize; 2]) -> usize {
+        self.web
+            .cells()
+            .iter()
+            .position(|cell| *cell == [r, c])
+            .expect("the cursor is always on a cell")
+    }
+
+    // The entry and pencil marks at `cell`
+    fn state(&self, cell: [usize; 2]) -> (usize, u64) {
+        let entry = self
+            .entries
+            .iter()
+            .find(|[r, c, _]| [*r, *c] == cell)
+            .map_or(0, |[_, _, n]| *n);
+        (entry, self.marks[self.mark_index(cell)])
+    }
+
+    fn set_state(&mut self, cell: [usize; 2], (entry, marks): (usize, u64)) {
+        let [r, c] = cell;
+        self.entries.retain(|[er, ec, _]| [*er, *ec] != cell);
+        if entry > 0 {
+            self.entries.push([r, c, entry]);
+        }
+        let i = self.mark_index(cell);
+        self.marks[i] = marks;
+        self.flagged.retain(|flagged| *flagged != cell);
+    }
+
+    // Change the cell at the cursor to `after`, as a move that can be undone
+    fn change(&mut self, after: (usize, u64)) {
+        let cell = self.cursor;
+        if self.is_given(cell[0], cell[1]) {
+            self.message = "That's a given".to_string();
+            return;
+        }
+        let before = self.state(cell);
+        if before == after {
+            return;
+        }
+        self.set_state(cell, after);
+        self.undo.push(Move {
+            cell,
+            before,
+            after,
+        });
+        self.redo.clear();
+        self.finish_if_solved();
+    }
+
+    // Enter `n` at the cursor, or toggle its pencil mark there
+    pub fn enter(&mut self, n: usize) {
+        if n < 1 || n > self.web.symbols() {
+            return;
+        }
+        let (entry, marks) = self.state(self.cursor);
+        if !self.pencil {
+            self.change((n, marks));
+        } else if entry == 0 {
+            self.change((0, marks ^ 1 << (n - 1)));
+        } else {
+            self.message = "Clear the cell before marking it".to_string();
+        }
+    }
+
+    // Clear the entry at the cursor, or its pencil marks if there's no entry
+    pub fn clear(&mut self) {
+        match self.state(self.cursor) {
+            (0, _) => self.change((0, 0)),
+            (_, marks) => self.change((0, marks)),
+        }
+    }
+
+    pub fn undo(&mut self) {
+        match self.undo.pop() {
+            Some(last) => {
+                self.set_state(last.cell, last.before);
+                self.cursor = last.cell;
+                self.redo.push(last);
+                self.finish_if_solved();
+            }
+            None => self.message = "Nothing to undo".to_string(),
+        }
+    }
+
+    pub fn redo(&mut self) {
+        match self.redo.pop() {
+            Some(next) => {
+                self.set_state(next.cell, next.after);
+                self.cursor = next.cell;
+                self.undo.push(next);
+                self.finish_if_solved();
+            }
+            None => self.message = "Nothing to redo".to_string(),
+        }
+    }
+
+    pub fn move_cursor(&mut self, dr: isize, dc: isize) {
+        let [mut r, mut c] = self.cursor;
+        // Skip over any gaps between grids
+        loop {
+            let (nr, nc) = (r as isize + dr, c as isize + dc);
+            if nr < 1 || nc < 1 || nr as usize > self.web.height() || nc as usize > self.web.width()
+            {
+                return;
+            }
+            r = nr as usize;
+            c = nc as usize;
+            if self.web.in_board(r, c) {
+                self.cursor = [r, c];
+                return;
+            }
+        }
+    }
+
+    // The entries that don't match the solution
+    pub fn wrong(&self) -> Vec<[usize; 2]> {
+        self.entries
+            .iter()
+            .filter(|id| !self.solution.contains(id))
+            .map(|[r, c, _]| [*r, *c])
+            .collect()
+    }
+
+    // Check the entries so far against the solution, and flag the wrong ones
+    pub fn check(&mut self) {
+        self.flagged = self.wrong();
+        self.message = match self.flagged.len() {
+            0 => format!("All {} entries so far are right", self.entries.len()),
+            1 => "1 entry is wrong".to_string(),
+            wrong => format!("{} entries are wrong", wrong),
+        };
+    }
+
+    // Point out the wrong entries, if there are any; otherwise give the easiest deduction left
+    // (see `hint_with`), making it if it places a symbol
+    pub fn hint(&mut self) {
+        let hint = self.web.hint_with(&self.entries, &self.solution);
+        self.message = hint.to_string();
+        match hint {
+            Hint::Wrong(cells) => {
+                self.hints += 1;
+                self.cursor = cells[0];
+                self.flagged = cells;
+            }
+            Hint::Step(step) => {
+                self.hints += 1;
+                self.cursor = step.cells[0];
+                if let Some([r, c, n]) = step.placement {
+                    self.cursor = [r, c];
+                    self.change((n, self.state([r, c]).1));
+                }
+            }
+            Hint::Solved => {}
+        }
+    }
+
+    pub fn solved(&self) -> bool {
+        self.web
+            .cells()
+            .iter()
+            .all(|[r, c]| self.solution.contains(&[*r, *c, self.value(*r, *c)]))
+    }
+
+    // Stop the clock once the puzzle is solved, and start it again if a move (or an undo) takes
+    // that back
+    fn finish_if_solved(&mut self) {
+        if self.solved() {
+            self.stop_clock();
+            self.message = format!(
+                "Solved in {}, with {} hint{}!",
+                clock(self.elapsed()),
+                self.hints,
+                if self.hints == 1 { "" } else { "s" }
+            );
+        } else if self.since.is_none() {
+            self.since = Some(Instant::now());
+        }
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed + self.since.map_or(Duration::ZERO, |since| since.elapsed())
+    }
+
+    pub fn stop_clock(&mut self) {
+        self.elapsed = self.elapsed();
+        self.since = None;
+    }
+
+    // Start the clock again from `elapsed` (for a game picked up where it was left), unless the
+    // game is over
+    pub fn set_elapsed(&mut self, elapsed: Duration) {
+        self.elapsed = elapsed;
+        self.since = if self.solved() {
+            None
+        } else {
+            Some(Instant::now())
+        };
+    }
+
+    // Show `message` below the grid, until the next key
+    pub fn say(&mut self, message: String) {
+        self.message = message;
+    }
+
+    // Act on a key
+    pub fn key(&mut self, key: Key) -> Action {
+        self.message.clear();
+        let nums = self.web.symbols();
+        match key {
+            Key::Up | Key::Char('k') => self.move_cursor(-1, 0),
+            Key::Down | Key::Char('j') => self.move_cursor(1, 0),
+            Key::Left | Key::Char('h') => self.move_cursor(0, -1),
+            Key::Right | Key::Char('l') => self.move_cursor(0, 1),
+            Key::Backspace | Key::Char('0') | Key::Char('.') | Key::Char(' ') => self.clear(),
+            Key::Char(d @ '1'..='9') => self.enter(d as usize - '0' as usize),
+            Key::Char(a @ 'A'..='Z') if nums > 9 => self.enter(a as usize - 'A' as usize + 10),
+            Key::Char('p') => self.pencil = !self.pencil,
+            Key::Char('u') => self.undo(),
+            Key::Char('r') => self.redo(),
+            Key::Char('?') => self.hint(),
+            Key::Char('c') => self.check(),
+            Key::Char('s') => return Action::Save,
+            Key::Char('n') => return Action::NewGame,
+            Key::Char('q') => return Action::Quit,
+            Key::Char(_) => {}
+        }
+        Action::Continue
+    }
+
+    // The whole screen, for a terminal `size` lines by columns, in color or not (see
+    // `color_stdout`). Pencil marks are shown when the larger grid they need fits.
+    pub fn screen(&self, size: [usize; 2], color: bool) -> String {
+        let board = self.board();
+        let grid = self
+            .web
+            .grid(&board)
+            .charset(Charset::Unicode)
+            .color(color)
+            .cursor(self.cursor)
+            .flag(&self.flagged);
+
+        let nums = self.web.symbols();
+        let across = (1..=nums).find(|a| a * a >= nums).unwrap_or(1);
+        let down = nums.div_ceil(across);
+        let inner = across * (nums.to_string().len() + 1) + 1;
+        let wide = self.web.width() * (inner + 1) + 1;
+        let tall = self.web.height() * (down + 1) + 1 + 4;
+        let grid = if wide <= size[1] && tall <= size[0] {
+            grid.marks(&self.marks).to_string()
+        } else {
+            grid.to_string()
+        };
+
+        let mut screen = format!(
+            "{}  {}  hints: {}  {}\n",
+            self.difficulty.map_or("Sudoku", |d| d.name()),
+            clock(self.elapsed()),
+            self.hints,
+            if self.pencil { "[pencil]" } else { "" }
+        );
+        screen += &grid;
+        screen += HELP;
+        screen += &self.message;
+        screen += "\n";
+        screen
+    }
+}
+
+// `m:ss`, or `h:mm:ss` past an hour
+fn clock(elapsed: Duration) -> String {
+    let secs = elapsed.as_secs();
+    if secs >= 3600 {
+        format!("{}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+    } else {
+        format!("{}:{:02}", secs / 60, secs % 60)
+    }
+}
+
+// Run `stty` on the terminal, returning what it prints
+fn stty(args: &[&str]) -> io::Result<String> {
+    let output = Command::new("stty")
+        .args(args)
+        .stdin(Stdio::inherit())
+        .output()?;
+    if !output.status.success() {
+        return Err(io::Error::other(format!("stty {} failed", args.join(" "))));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+// The terminal's size, as lines and columns. This runs `stty`, so it's not something to do on
+// every key.
+fn terminal_size() -> [usize; 2] {
+    let size = stty(&["size"]).unwrap_or_default();
+    let mut words = size.split_whitespace().map(|w| w.parse().unwrap_or(0));
+    match (words.next(), words.next()) {
+        (Some(lines), Some(columns)) if lines > 0 && columns > 0 => [lines, columns],
+        _ => [24, 80],
+    }
+}
+
+// The terminal in raw mode, showing the alternate screen, for as long as this lives. Dropping it
+// puts the terminal back as it was, however the game ends: quitting, an error, or a panic.
+struct RawScreen {
+    // What `stty -g` said before the terminal was changed
+    saved: String,
+}
+
+impl RawScreen {
+    fn enter(out: &mut io::Stdout) -> io::Result<RawScreen> {
+        let screen = RawScreen {
+            saved: stty(&["-g"])?,
+        };
+        // Raw, and reads that give up after a tenth of a second, so the clock keeps ticking
+        stty(&["raw", "-echo", "min", "0", "time", "1"])?;
+        // The alternate screen, without the cursor
+        write!(out, "\x1b[?1049h\x1b[?25l")?;
+        out.flush()?;
+        Ok(screen)
+    }
+}
+
+impl Drop for RawScreen {
+    fn drop(&mut self) {
+        let mut out = io::stdout();
+        // There's nowhere left to report a failure to, so this does what it can
+        let _ = write!(out, "\x1b[?25h\x1b[?1049l");
+        let _ = out.flush();
+        let _ = stty(&[&self.saved]);
+    }
+}
+
+// Play games with boxes `belts` wide and `curtains` tall, starting at `difficulty`, until the
+// player quits. With a `session` file, the game saved there is picked up if there is one, and the
+// game is saved there on the way out.
+//
+// Without colors (see `color_stdout`), the cursor is shown in reverse video, and nothing else is
+// picked out.
+//
+pub fn play(
+    belts: usize,
+    curtains: usize,
+    difficulty: Difficulty,
+    session: Option<&Path>,
+) -> io::Result<()> {
+    if !io::stdin().is_terminal() || !io::stdout().is_terminal() {
+        return Err(io::Error::other("the game needs a terminal"));
+    }
+    let mut out = io::stdout();
+    let game = match session {
+        Some(path) if path.exists() => Game::load_file(path).map_err(io::Error::other)?,
+        _ => {
+            write!(out, "Generating a puzzle...\r\n")?;
+            out.flush()?;
+            Game::random(belts, curtains, difficulty)
+        }
+    };
+
+    let _raw = RawScreen::enter(&mut out)?;
+    run(game, &mut out, session)
+}
+
+fn run(mut game: Game, out: &mut io::Stdout, session: Option<&Path>) -> io::Result<()> {
+    let mut stdin = io::stdin();
+    let mut buf = [0; 32];
+    let mut shown = String::new();
+    let color = color_stdout();
+    let mut size = terminal_size();
+    let mut sized = Instant::now();
+    loop {
+        if sized.elapsed() >= RESIZE_EVERY {
+            size = terminal_size();
+            sized = Instant::now();
+        }
+        let screen = game.screen(size, color);
+        if screen != shown {
+            // Home, each line cleared to its end, and the rest of the screen cleared
+            let lines: Vec<&str> = screen.lines().collect();
+            write!(out, "\x1b[H{}\x1b[K\x1b[J", lines.join("\x1b[K\r\n"))?;
+            out.flush()?;
+            shown = screen;
+        }
+
+        let read = stdin.read(&mut buf)?;
+        for key in parse_keys(&buf[..read]) {
+            match game.key(key) {
+                Action::Continue => {}
+                Action::Save => match session {
+                    Some(path) => match game.save_file(path) {
+                        Ok(()) => game.say(format!("Saved to {}", path.display())),
+                        Err(err) => game.say(err),
+                    },
+                    None => game.say("There's no session file to save to".to_string()),
+                },
+                Action::Quit => {
+                    if let Some(path) = session {
+                        game.save_file(path).map_err(io::Error::other)?;
+                    }
+                    return Ok(());
+                }
+                Action::NewGame => {
+                    write!(out, "\x1b[H\x1b[JGenerating a puzzle...")?;
+                    out.flush()?;
+                    let [belts, curtains] = [game.web.belts, game.web.curtains];
+                    let difficulty = game.difficulty.unwrap_or(Difficulty::Medium);
+                    game = Game::random(belts, curtains, difficulty);
+                    shown.clear();
+                }
+            }
+        }
+    }
+}
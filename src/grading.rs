@@ -0,0 +1,92 @@
+// How hard a puzzle is, judged by how much `dance` has to guess to prove its solution unique
+//
+// Every cell left empty takes at least one row of the search. A puzzle that takes no more than
+// that never needs a guess: some cell or some unit always has just one way left, so it falls to
+// naked and hidden singles. Beyond that, the more rows tried and given back, the harder it is to
+// solve by hand. Searching by the shortest column is close enough to how people look for the most
+// constrained place to work on that this tracks them reasonably well.
+//
+
+use limits::Outcome;
+use {Backend, Knowing, SudokuWeb};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Difficulty {
+    // Singles all the way
+    Easy,
+    // Up to half again as many rows as empty cells
+    Medium,
+    // Up to three times as many
+    Hard,
+    // More
+    Fiendish,
+}
+
+impl Difficulty {
+    pub const ALL: [Difficulty; 4] = [
+        Difficulty::Easy,
+        Difficulty::Medium,
+        Difficulty::Hard,
+        Difficulty::Fiendish,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Difficulty::Easy => "Easy",
+            Difficulty::Medium => "Medium",
+            Difficulty::Hard => "Hard",
+            Difficulty::Fiendish => "Fiendish",
+        }
+    }
+}
+
+// A generated puzzle: its clues, its (unique) solution, and how hard it is
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Puzzle {
+    pub clues: Vec<[usize; 3]>,
+    pub solution: Vec<[usize; 3]>,
+    pub difficulty: Difficulty,
+}
+
+impl SudokuWeb {
+    // Grade the puzzle in `prop_solution`. `None` if it doesn't have a unique solution, or the
+    // web's `limits` were reached before that was known.
+    //
+    // It is always danced, even when there are rules, since the propagating solver guesses far
+    // less than a person could.
+    //
+    pub fn grade(&mut self) -> Option<Difficulty> {
+        let backend = self.backend;
+        self.backend = Backend::Dance;
+        let stats = self.solve_traced(2, false, false, None);
+        self.backend = backend;
+        if stats.outcome != Outcome::Finished || self.uniq != Knowing::Yes {
+            return None;
+        }
+
+        let empty = self.cells().len() - self.prop_solution.len();
+        let extra = stats.nodes().saturating_sub(empty);
+        Some(if extra == 0 {
+            Difficulty::Easy
+        } else if 2 * extra <= empty {
+            Difficulty::Medium
+        } else if extra <= 2 * empty {
+            Difficulty::Hard
+        } else {
+            Difficulty::Fiendish
+        })
+    }
+
+    // `random_puzzle`, graded. `None` if a limit was reached first, or the web has no solution.
+    pub fn generate(&mut self) -> Option<Puzzle> {
+        if self.random_puzzle() != Ok(Outcome::Finished) {
+            return None;
+        }
+        let difficulty = self.grade()?;
+        Some(Puzzle {
+            clues: self.prop_solution.clone(),
+            solution: self.solution.clone(),
+            difficulty,
+        })
+    }
+}
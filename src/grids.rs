@@ -0,0 +1,236 @@
+// Boards made of several overlapping grids, like samurai sudoku
+//
+// The columns for such a board are decided by `SudokuWeb::index_columns`. This covers the rest:
+// laying the grids out, and drawing the combined board.
+//
+
+use render::{highlight, paint, Charset, Colors};
+use std::fmt;
+use SudokuWeb;
+
+impl SudokuWeb {
+    // Five grids, one in each corner and one in the middle, with the middle grid sharing a corner
+    // box with each of the others.
+    //
+    // For the usual samurai sudoku, call `samurai(3, 3)`.
+    //
+    pub fn samurai(belts: usize, curtains: usize) -> Self {
+        let nums = belts * curtains;
+        // Boxes are `curtains` cells tall and `belts` cells wide
+        let (down, across) = (nums - curtains, nums - belts);
+        let grids = vec![
+            [0, 0],
+            [0, 2 * across],
+            [down, across],
+            [2 * down, 0],
+            [2 * down, 2 * across],
+        ];
+        Self::with_grids(belts, curtains, grids)
+    }
+
+    // Every cell on the board that belongs to some grid, top to bottom, then left to right
+    pub fn cells(&self) -> Vec<[usize; 2]> {
+        (1..=self.height())
+            .flat_map(|r| (1..=self.width()).map(move |c| [r, c]))
+            .filter(|[r, c]| self.in_grid(*r, *c))
+            .collect()
+    }
+
+    // Like `fmt_solution`, but for any arrangement of grids. Only the cells that belong to a grid
+    // are drawn; box edges, and the edges of the board, are drawn with double lines.
+    #[allow(clippy::needless_range_loop)]
+    pub fn fmt_board(
+        &self,
+        f: &mut dyn fmt::Write,
+        sol: &[[usize; 3]],
+        charset: Charset,
+        colors: Option<&Colors>,
+    ) -> fmt::Result {
+        let (height, width) = (self.height(), self.width());
+        let mut a = vec![vec!["".to_string(); width + 1]; height + 1];
+        let mut sym_width = 2;
+        for [r, c, n] in sol.iter() {
+            let n = n.to_string();
+            if sym_width < n.len() {
+                sym_width = n.len();
+            }
+            a[*r][*c] = n;
+        }
+
+        for r in 1..=(height + 1) {
+            writeln!(f, "{}", self.line_above(r, sym_width, charset))?;
+            if r > height {
+                break;
+            }
+            let mut line = String::new();
+            for c in 1..=(width + 1) {
+                line.push(self.line_left(r, c, charset));
+                if c <= width {
+                    line.push_str(&paint(colors, r, c, &a[r][c], sym_width));
+                }
+            }
+            writeln!(f, "{}", line.trim_end())?;
+        }
+        Ok(())
+    }
+
+    // Like `fmt_board`, but with room in each cell for a little grid of pencil marks, as square
+    // as it can be. `marks` has a bit mask for each of `cells()`, like `prop_candidates`. Cells
+    // filled in `sol` show their symbol in the middle instead.
+    #[allow(clippy::needless_range_loop)]
+    pub fn fmt_marks(
+        &self,
+        f: &mut dyn fmt::Write,
+        sol: &[[usize; 3]],
+        marks: &[u64],
+        charset: Charset,
+        colors: Option<&Colors>,
+    ) -> fmt::Result {
+        let (height, width, nums) = (self.height(), self.width(), self.symbols());
+        let across = (1..=nums).find(|a| a * a >= nums).unwrap_or(1);
+        let down = nums.div_ceil(across);
+        let sym_width = nums.to_string().len();
+        // A space before each mark, and one after the last
+        let inner = across * (sym_width + 1) + 1;
+
+        let mut a = vec![vec!["".to_string(); width + 1]; height + 1];
+        for [r, c, n] in sol.iter() {
+            a[*r][*c] = n.to_string();
+        }
+        let mut masks = vec![vec![0; width + 1]; height + 1];
+        for ([r, c], mask) in self.cells().into_iter().zip(marks) {
+            masks[r][c] = *mask;
+        }
+
+        for r in 1..=(height + 1) {
+            writeln!(f, "{}", self.line_above(r, inner, charset))?;
+            if r > height {
+                break;
+            }
+            for i in 0..down {
+                let mut line = String::new();
+                for c in 1..=(width + 1) {
+                    line.push(self.line_left(r, c, charset));
+                    if c > width {
+                        break;
+                    }
+                    if !a[r][c].is_empty() {
+                        let left = (inner - sym_width) / 2;
+                        if i == down / 2 {
+                            line.push_str(&paint(colors, r, c, &a[r][c], left + sym_width));
+                            let right = " ".repeat(inner - left - sym_width);
+                            line.push_str(&highlight(colors, r, c, &right));
+                        } else {
+                            line.push_str(&highlight(colors, r, c, &" ".repeat(inner)));
+                        }
+                        continue;
+                    }
+                    let mut text = String::new();
+                    for n in (i * across + 1)..=((i + 1) * across) {
+                        text.push(' ');
+                        if n <= nums && masks[r][c] & 1 << (n - 1) != 0 {
+                            text.push_str(&format!("{:>w$}", n, w = sym_width));
+                        } else {
+                            text.push_str(&" ".repeat(sym_width));
+                        }
+                    }
+                    text.push(' ');
+                    line.push_str(&highlight(colors, r, c, &text));
+                }
+                writeln!(f, "{}", line.trim_end())?;
+            }
+        }
+        Ok(())
+    }
+
+    // The line above row `r`, through each corner, with cells `inner` characters wide
+    fn line_above(&self, r: usize, inner: usize, charset: Charset) -> String {
+        let mut line = String::new();
+        for c in 1..=(self.width() + 1) {
+            line.push(charset.junction(self.junction(r, c)));
+            if c <= self.width() {
+                let h = charset.horiz(self.horiz_edge(r, c));
+                for _ in 0..inner {
+                    line.push(h);
+                }
+            }
+        }
+        line.trim_end().to_string()
+    }
+
+    // The line to the left of the cell at `r`, `c`, if any
+    fn line_left(&self, r: usize, c: usize, charset: Charset) -> char {
+        match self.vert_edge(r, c) {
+            0 => ' ',
+            weight => charset.vert(weight == 2),
+        }
+    }
+
+    pub fn in_board(&self, r: usize, c: usize) -> bool {
+        r >= 1 && c >= 1 && r <= self.height() && c <= self.width() && self.in_grid(r, c)
+    }
+
+    // The weight of the edge above the cell at `r`, `c`: 0 for none, 1 for thin, 2 for thick
+    pub fn horiz_edge(&self, r: usize, c: usize) -> usize {
+        if c < 1 || c > self.width() {
+            return 0;
+        }
+        match (self.in_board(r - 1, c), self.in_board(r, c)) {
+            (false, false) => 0,
+            (true, true) if self.same_block(r - 1, c, r, c) => 1,
+            _ => 2,
+        }
+    }
+
+    // The weight of the edge to the left of the cell at `r`, `c`
+    pub fn vert_edge(&self, r: usize, c: usize) -> usize {
+        if r < 1 || r > self.height() {
+            return 0;
+        }
+        match (self.in_board(r, c - 1), self.in_board(r, c)) {
+            (false, false) => 0,
+            (true, true) if self.same_block(r, c - 1, r, c) => 1,
+            _ => 2,
+        }
+    }
+
+    fn same_block(&self, r1: usize, c1: usize, r2: usize, c2: usize) -> bool {
+        let block = |r: usize, c: usize| self.indices_from_rcn(r, c, 1)[3];
+        block(r1, c1) == block(r2, c2)
+    }
+
+    // The character where the corners of the cells at `r - 1`, `c - 1` and `r`, `c` meet
+    fn junction(&self, r: usize, c: usize) -> char {
+        let mut up = self.vert_edge(r - 1, c);
+        let mut down = self.vert_edge(r, c);
+        let mut left = self.horiz_edge(r, c - 1);
+        let mut right = self.horiz_edge(r, c);
+        // There are no characters that mix weights along one line
+        if up > 0 && down > 0 {
+            up = up.max(down);
+            down = up;
+        }
+        if left > 0 && right > 0 {
+            left = left.max(right);
+            right = left;
+        }
+        let v = up.max(down);
+        let h = left.max(right);
+        // Indexed by `(v == 2) as usize * 2 + (h == 2) as usize`
+        let chars = match (up > 0, down > 0, left > 0, right > 0) {
+            (false, false, false, false) => return ' ',
+            (true, true, true, true) => ['┼', '╪', '╫', '╬'],
+            (true, true, false, true) => ['├', '╞', '╟', '╠'],
+            (true, true, true, false) => ['┤', '╡', '╢', '╣'],
+            (false, true, true, true) => ['┬', '╤', '╥', '╦'],
+            (true, false, true, true) => ['┴', '╧', '╨', '╩'],
+            (false, true, false, true) => ['┌', '╒', '╓', '╔'],
+            (false, true, true, false) => ['┐', '╕', '╖', '╗'],
+            (true, false, false, true) => ['└', '╘', '╙', '╚'],
+            (true, false, true, false) => ['┘', '╛', '╜', '╝'],
+            (_, _, false, false) => ['│', '│', '║', '║'],
+            (false, false, _, _) => ['─', '═', '─', '═'],
+        };
+        chars[(v == 2) as usize * 2 + (h == 2) as usize]
+    }
+}
@@ -0,0 +1,92 @@
+// Puzzles and solutions as HTML tables, to drop into a page
+//
+// Each cell is a `td` with its borders set from `horiz_edge` and `vert_edge`, thick or thin, like
+// `to_svg` draws them; cells off of every grid are left blank and without borders. Givens
+// (`prop_solution`) are bold, the rest of a solution blue, and pencil marks small and grey, in a
+// little grid in their cell.
+//
+
+use SudokuWeb;
+
+const STYLE: &str = "table.sudoku{border-collapse:collapse;font-family:sans-serif}\
+.sudoku td{width:40px;height:40px;padding:0;text-align:center;vertical-align:middle;\
+font-size:24px}\
+.sudoku td.given{font-weight:bold;color:#000}\
+.sudoku td.solved{color:#2a5db0}\
+.sudoku .marks{display:grid;font-size:10px;line-height:1;color:#666}";
+
+// The CSS for one side of a cell's border, of the weight `horiz_edge` or `vert_edge` gives
+fn border(side: &str, weight: usize) -> String {
+    match weight {
+        0 => String::new(),
+        1 => format!("border-{}:1px solid #999;", side),
+        _ => format!("border-{}:3px solid #000;", side),
+    }
+}
+
+impl SudokuWeb {
+    // `sol`: what to fill in, besides the givens (which needn't be repeated)
+    // `marks`: pencil marks for the cells left empty, with a bit mask for each of `cells()` like
+    // `prop_candidates` (or none at all)
+    pub fn to_html(&self, sol: &[[usize; 3]], marks: &[u64]) -> String {
+        let (height, width, nums) = (self.height(), self.width(), self.symbols());
+        let across = (1..=nums).find(|a| a * a >= nums).unwrap_or(1);
+        let down = nums.div_ceil(across);
+
+        let mut filled = vec![];
+        for (class, ids) in [("given", &self.prop_solution[..]), ("solved", sol)] {
+            for [r, c, n] in ids.iter() {
+                if !filled.iter().any(|(cell, _, _)| *cell == [*r, *c]) {
+                    filled.push(([*r, *c], class, *n));
+                }
+            }
+        }
+        let mut masks = vec![0; (height + 1) * (width + 1)];
+        for ([r, c], mask) in self.cells().into_iter().zip(marks) {
+            masks[r * (width + 1) + c] = *mask;
+        }
+
+        let mut html = format!(
+            "<style>{}.sudoku .marks{{grid-template-columns:repeat({},1fr)}}</style>\n",
+            STYLE, across
+        );
+        html += "<table class=\"sudoku\">\n";
+        for r in 1..=height {
+            html += "<tr>";
+            for c in 1..=width {
+                if !self.in_board(r, c) {
+                    html += "<td></td>";
+                    continue;
+                }
+                let style = border("top", self.horiz_edge(r, c))
+                    + &border("bottom", self.horiz_edge(r + 1, c))
+                    + &border("left", self.vert_edge(r, c))
+                    + &border("right", self.vert_edge(r, c + 1));
+                match filled.iter().find(|(cell, _, _)| *cell == [r, c]) {
+                    Some((_, class, n)) => {
+                        html += &format!("<td class=\"{}\" style=\"{}\">{}</td>", class, style, n)
+                    }
+                    None => {
+                        let mask = masks[r * (width + 1) + c];
+                        html += &format!("<td style=\"{}\">", style);
+                        if mask != 0 {
+                            html += "<div class=\"marks\">";
+                            for n in 1..=(across * down) {
+                                if n <= nums && mask & 1 << (n - 1) != 0 {
+                                    html += &format!("<span>{}</span>", n);
+                                } else {
+                                    html += "<span></span>";
+                                }
+                            }
+                            html += "</div>";
+                        }
+                        html += "</td>";
+                    }
+                }
+            }
+            html += "</tr>\n";
+        }
+        html += "</table>\n";
+        html
+    }
+}
@@ -0,0 +1,2057 @@
+// The solver, as a library: the dancing links engine (see the `exact_cover` module), which
+// solves any exact cover problem, not just sudoku, and `SudokuWeb`, a sudoku built on it, with
+// everything that can be done with one. The binary (`main`) plays games and runs experiments with
+// it.
+//
+
+extern crate rand;
+extern crate slab;
+
+pub mod bitmap;
+pub mod dimacs;
+pub mod exact_cover;
+pub mod font;
+pub mod game;
+pub mod grading;
+pub mod grids;
+pub mod html;
+pub mod limits;
+pub mod lines;
+pub mod logic;
+pub mod matrix;
+pub mod pdf;
+pub mod propagation;
+pub mod raster;
+pub mod relations;
+pub mod render;
+pub mod rules;
+pub mod session;
+pub mod shared;
+pub mod svg;
+pub mod trace;
+pub mod variants;
+pub mod walkthrough;
+
+use exact_cover::{ExactCover, Hook};
+use limits::{Budget, Limits, Outcome};
+use rand::Rng;
+use render::{color_stdout, paint, Charset, Colors};
+use rules::{Board, Rule};
+use std::collections::HashMap;
+use std::fmt;
+use std::mem;
+use trace::Tracer;
+use variants::Variant;
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Knowing {
+    Yes,
+    No,
+    Unknown,
+}
+
+// How `solve` searches: by dancing, or by propagating constraints (see the `propagation`
+// module). `Auto` propagates only when there are rules to obey.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Backend {
+    Auto,
+    Dance,
+    Propagate,
+}
+
+// A sudoku as an exact cover problem
+//
+// The dancing links themselves are in `cover` (see the `exact_cover` module), with a row labelled
+// `[r, c, n]` for each symbol each cell may hold; `rows` finds the row for each `[r, c, n]`.
+//
+// The primary columns are described by `column_keys`, and `columns_of` lists the columns each
+// `[r, c, n]` row belongs to (see `index_columns`). After them come the `secondary` columns,
+// which may be covered at most once, instead of exactly once.
+//
+// Anything else a solution must obey goes in `rules` (see the `rules` module), which `dance`
+// checks against `board`, the rows it has chosen so far.
+//
+#[derive(Debug)]
+pub struct SudokuWeb {
+    cover: ExactCover<[usize; 3]>,
+    grids: Vec<[usize; 2]>,
+    variants: Vec<Variant>,
+    secondary: Vec<Vec<[usize; 3]>>,
+    column_keys: Vec<[usize; 4]>,
+    columns_of: Vec<Vec<usize>>,
+    allowed: Option<Vec<u64>>,
+    rows: Vec<Option<usize>>,
+    pub rules: Vec<Box<dyn Rule>>,
+    board: Board,
+    pub backend: Backend,
+    pub limits: Limits,
+    pub solution: Vec<[usize; 3]>,
+    pub prop_solution: Vec<[usize; 3]>,
+    pub prop_candidates: Vec<u64>,
+    pub solution_count: usize,
+    belts: usize,
+    curtains: usize,
+    pub uniq: Knowing,
+    pub possible: Knowing,
+}
+
+impl SudokuWeb {
+    // `belts`: The number of rows of big boxes (each with the same number of rows of individual
+    // cells).
+    // `curtains`: The number of columns of big boxes (each with the same number of columns of
+    // individual cells).
+    //
+    // For a regular sudoku, call `new(3, 3)`.
+    //
+    // This prepares the cover and populates it with rows, in preparation for their dance.
+    //
+    pub fn new(belts: usize, curtains: usize) -> Self {
+        Self::with_secondary(belts, curtains, vec![])
+    }
+
+    // Like `new`, but with extra secondary columns. Each secondary column is the list of
+    // `[r, c, n]` rows that it contains, and at most one of those rows can be in a solution.
+    //
+    pub fn with_secondary(belts: usize, curtains: usize, secondary: Vec<Vec<[usize; 3]>>) -> Self {
+        Self::assemble(belts, curtains, vec![[0, 0]], secondary, vec![])
+    }
+
+    // Like `new`, but each cell may only hold some of the symbols. `allowed` has a bit mask for
+    // each cell (top to bottom, then left to right), with bit `n - 1` set if the cell may hold
+    // `n`. The rows for the other symbols are left out of the web entirely. A mask only has room
+    // for 64 symbols, so there can't be more.
+    //
+    pub fn with_allowed(belts: usize, curtains: usize, allowed: &[u64]) -> Self {
+        Self::assemble(belts, curtains, vec![[0, 0]], vec![], allowed.to_vec())
+    }
+
+    // A parity puzzle: each character of `puzzle` is a cell, top to bottom, then left to right.
+    // A digit from 1 up is a given, `e` is a cell that must be even, `o` is a cell that must be
+    // odd, and anything else (`0` too) is an empty cell. The givens end up in `prop_solution`.
+    //
+    pub fn parity(belts: usize, curtains: usize, puzzle: &str) -> Self {
+        let nums = belts * curtains;
+        assert!(nums <= 64, "parity puzzles can't have more than 64 symbols");
+        let mask = |keep: &dyn Fn(usize) -> bool| {
+            (1..=nums)
+                .filter(|n| keep(*n))
+                .fold(0, |mask, n| mask | 1 << (n - 1))
+        };
+        let (all, evens, odds) = (
+            mask(&|_| true),
+            mask(&|n| n % 2 == 0),
+            mask(&|n| n % 2 == 1),
+        );
+        let allowed: Vec<u64> = puzzle
+            .chars()
+            .map(|ch| match ch {
+                'e' => evens,
+                'o' => odds,
+                _ => all,
+            })
+            .collect();
+
+        let mut sw = Self::with_allowed(belts, curtains, &allowed);
+        sw.prop_solution = puzzle
+            .chars()
+            .enumerate()
+            .filter_map(|(i, ch)| {
+                ch.to_digit(10)
+                    .map(|n| n as usize)
+                    .filter(|n| (1..=nums).contains(n))
+                    .map(|n| [i / nums + 1, i % nums + 1, n])
+            })
+            .collect();
+        sw
+    }
+
+    // Like `new`, but the solutions (and so the generated puzzles) must also obey each of the
+    // given variants.
+    //
+    // For an anti-knight sudoku, call `with_variants(3, 3, &[Variant::AntiKnight])`.
+    //
+    pub fn with_variants(belts: usize, curtains: usize, variants: &[Variant]) -> Self {
+        let nums = belts * curtains;
+        let secondary = variants
+            .iter()
+            .flat_map(|v| v.secondary_columns(nums))
+            .collect();
+        let mut sw = Self::with_secondary(belts, curtains, secondary);
+        sw.variants = variants.to_vec();
+        sw
+    }
+
+    // Like `new`, but with several overlapping grids solved as one puzzle. `grids` holds the
+    // offset (in rows and columns of cells) of each grid's top left cell from the top left of the
+    // whole board. Grids should overlap on whole boxes; the cells and boxes they share only get
+    // one column each.
+    //
+    // For a samurai sudoku, call `samurai(3, 3)`.
+    //
+    pub fn with_grids(belts: usize, curtains: usize, grids: Vec<[usize; 2]>) -> Self {
+        Self::assemble(belts, curtains, grids, vec![], vec![])
+    }
+
+    // `allowed` is as for `with_allowed`; it can be empty to allow everything.
+    fn assemble(
+        belts: usize,
+        curtains: usize,
+        grids: Vec<[usize; 2]>,
+        secondary: Vec<Vec<[usize; 3]>>,
+        allowed: Vec<u64>,
+    ) -> Self {
+        let mut sw = Self {
+            cover: ExactCover::new(0, 0),
+            grids,
+            variants: vec![],
+            secondary,
+            column_keys: vec![],
+            columns_of: vec![],
+            allowed: None,
+            rows: vec![],
+            rules: vec![],
+            board: Board::new(0, 0, 0),
+            backend: Backend::Auto,
+            limits: Limits::default(),
+            solution: vec![],
+            prop_solution: vec![],
+            prop_candidates: vec![],
+            solution_count: 0,
+            uniq: Knowing::Unknown,
+            possible: Knowing::Unknown,
+            belts,
+            curtains,
+        };
+
+        sw.index_columns();
+        if !allowed.is_empty() {
+            assert!(
+                sw.symbols() <= 64,
+                "a web with allowed symbols can't have more than 64 symbols"
+            );
+            let mut masks = vec![!0; sw.height() * sw.width()];
+            for ([r, c], mask) in sw.cells().into_iter().zip(allowed) {
+                masks[(r - 1) * sw.width() + (c - 1)] = mask;
+            }
+            sw.allowed = Some(masks);
+        }
+
+        sw.rows = vec![None; sw.possibilities()];
+        sw.board = Board::new(sw.height(), sw.width(), sw.symbols());
+
+        sw.cover = ExactCover::new(sw.constraints(), sw.secondary.len());
+        let nodes = sw.nodes();
+        sw.cover.reserve(nodes);
+        sw.populate_rows();
+        sw
+    }
+
+    pub fn symbols(&self) -> usize {
+        self.belts * self.curtains
+    }
+
+    // The number of rows of cells on the whole board
+    pub fn height(&self) -> usize {
+        self.grids.iter().map(|g| g[0]).max().unwrap_or(0) + self.symbols()
+    }
+
+    // The number of columns of cells on the whole board
+    pub fn width(&self) -> usize {
+        self.grids.iter().map(|g| g[1]).max().unwrap_or(0) + self.symbols()
+    }
+
+    fn constraints(&self) -> usize {
+        self.column_keys.len()
+    }
+
+    fn columns(&self) -> usize {
+        self.constraints() + self.secondary.len()
+    }
+
+    fn possibilities(&self) -> usize {
+        self.height() * self.width() * self.symbols()
+    }
+
+    fn nodes(&self) -> usize {
+        self.columns_of.iter().map(|c| c.len()).sum()
+    }
+
+    fn possibility_index(&self, r: usize, c: usize, n: usize) -> usize {
+        let nums = self.symbols();
+        ((r - 1) * self.width() + (c - 1)) * nums + (n - 1)
+    }
+
+    // Does any grid contain the cell at `r`, `c` (on the whole board)?
+    fn in_grid(&self, r: usize, c: usize) -> bool {
+        !self.columns_of[self.possibility_index(r, c, 1)].is_empty()
+    }
+
+    // The bit mask of the symbols the cell at `r`, `c` may hold
+    fn allowed_at(&self, r: usize, c: usize) -> u64 {
+        match self.allowed.as_ref() {
+            Some(allowed) => allowed[(r - 1) * self.width() + (c - 1)],
+            None => !0,
+        }
+    }
+
+    // May the cell at `r`, `c` hold `n`? Without any masks, it may hold anything (however many
+    // symbols there are).
+    fn allows(&self, r: usize, c: usize, n: usize) -> bool {
+        self.allowed.is_none() || self.allowed_at(r, c) & 1 << (n - 1) != 0
+    }
+
+    // Decide on every column, and record which columns each `[r, c, n]` row belongs to, so
+    // `indices_from_rcn` can find them without searching.
+    //
+    // Each column has a key: `[0, r, c, 0]` for a cell, `[1, g, r, n]` for a row and
+    // `[2, g, c, n]` for a column of grid `g` (with `r` and `c` counted within the grid), and
+    // `[3, r, c, n]` for a block whose top left cell is at `r`, `c`. Cells and blocks shared by
+    // several grids share their columns.
+    //
+    fn index_columns(&mut self) {
+        let nums = self.symbols();
+        let mut keys: Vec<[usize; 4]> = vec![];
+        let mut key_indices: HashMap<[usize; 4], usize> = HashMap::new();
+
+        let cases = ["cell", "row", "column", "block"];
+        for (case_index, _case_name) in cases.iter().enumerate() {
+            for (g, [ro, co]) in self.grids.iter().enumerate() {
+                for i in 1..=nums {
+                    for j in 1..=nums {
+                        let key = match case_index {
+                            0 => [0, ro + i, co + j, 0],
+                            1 | 2 => [case_index, g, i, j],
+                            _ => {
+                                let top = ro + ((i - 1) / self.curtains) * self.curtains + 1;
+                                let left = co + ((i - 1) % self.curtains) * self.belts + 1;
+                                [3, top, left, j]
+                            }
+                        };
+                        key_indices.entry(key).or_insert_with(|| {
+                            keys.push(key);
+                            keys.len() - 1
+                        });
+                    }
+                }
+            }
+        }
+        self.column_keys = keys;
+
+        let mut columns_of = vec![vec![]; self.possibilities()];
+        for r in 1..=self.height() {
+            for c in 1..=self.width() {
+                for n in 1..=nums {
+                    let mut indices: Vec<usize> = vec![];
+                    for (g, [ro, co]) in self.grids.iter().enumerate() {
+                        if r <= *ro || c <= *co || r > ro + nums || c > co + nums {
+                            continue;
+                        }
+                        let top = ro + ((r - ro - 1) / self.curtains) * self.curtains + 1;
+                        let left = co + ((c - co - 1) / self.belts) * self.belts + 1;
+                        if indices.is_empty() {
+                            indices.push(key_indices[&[0, r, c, 0]]);
+                        }
+                        indices.push(key_indices[&[1, g, r - ro, n]]);
+                        indices.push(key_indices[&[2, g, c - co, n]]);
+                        let block = key_indices[&[3, top, left, n]];
+                        if !indices.contains(&block) {
+                            indices.push(block);
+                        }
+                    }
+                    columns_of[self.possibility_index(r, c, n)] = indices;
+                }
+            }
+        }
+
+        let first = self.constraints();
+        for (s_i, ids) in self.secondary.iter().enumerate() {
+            for [r, c, n] in ids.iter() {
+                columns_of[self.possibility_index(*r, *c, *n)].push(first + s_i);
+            }
+        }
+        self.columns_of = columns_of;
+    }
+
+    fn populate_rows(&mut self) {
+        let nums = self.symbols();
+
+        for r in 1..=self.height() {
+            for c in 1..=self.width() {
+                if !self.in_grid(r, c) {
+                    continue;
+                }
+                for n in 1..=nums {
+                    if self.allows(r, c, n) {
+                        self.insert_row(r, c, n);
+                    }
+                }
+            }
+        }
+    }
+
+    fn indices_from_rcn(&self, r: usize, c: usize, n: usize) -> Vec<usize> {
+        self.columns_of[self.possibility_index(r, c, n)].clone()
+    }
+
+    // The `[r, c, n]` rows in each column, primary columns first, then secondary columns
+    fn rows_of_columns(&self) -> Vec<Vec<[usize; 3]>> {
+        let mut rows = vec![vec![]; self.columns()];
+        for [r, c] in self.cells() {
+            for n in (1..=self.symbols()).filter(|n| self.allows(r, c, *n)) {
+                for i in self.indices_from_rcn(r, c, n) {
+                    rows[i].push([r, c, n]);
+                }
+            }
+        }
+        rows
+    }
+
+    fn insert_row(&mut self, r: usize, c: usize, n: usize) {
+        let indices = self.indices_from_rcn(r, c, n);
+        let row = self.cover.add_row([r, c, n], &indices);
+        let p_i = self.possibility_index(r, c, n);
+        self.rows[p_i] = Some(row);
+    }
+
+    // The row of the cover for `[r, c, n]`, unless it was left out
+    fn row_of(&self, r: usize, c: usize, n: usize) -> Option<usize> {
+        self.rows[self.possibility_index(r, c, n)]
+    }
+
+    // seek: Don't stop until this many solutions are found, or until there are no more solutions
+    // print: Whether to print the solution (before & after) or not
+    // rand: pick columns, and the order to try their rows in, randomly (good for generating
+    // puzzles, not for solving)
+    //
+    // Only the candidates in `prop_candidates` are considered (see `eliminate`), and the solution
+    // must include `prop_solution`.
+    //
+    // Returns whether the search finished, or stopped at one of the web's `limits`. `uniq` and
+    // `possible` only say what this search found out, not what an earlier one did.
+    //
+    pub fn solve(&mut self, seek: usize, print: bool, rand: bool) -> Outcome {
+        self.search(seek, print, rand, None)
+    }
+
+    // `solve`, with a `Tracer` for `dance` to report to if tracing (see `solve_traced`)
+    fn search(
+        &mut self,
+        seek: usize,
+        print: bool,
+        rand: bool,
+        tracer: Option<&mut Tracer>,
+    ) -> Outcome {
+        self.uniq = Knowing::Unknown;
+        self.possible = Knowing::Unknown;
+        let propagate = match self.backend {
+            Backend::Auto => !self.rules.is_empty(),
+            Backend::Dance => false,
+            Backend::Propagate => true,
+        };
+        if propagate {
+            return self.propagate(seek, print, rand);
+        }
+
+        self.solution = vec![];
+        self.solution_count = 0;
+
+        let pc = self.prop_candidates.clone();
+        let eliminated = self.eliminate(&pc);
+
+        let ps = self.prop_solution.clone();
+        self.possible = self.pre_dance(&ps);
+
+        let mut outcome = Outcome::Finished;
+        if self.possible != Knowing::No {
+            let cells = self.cells();
+            let pruned = self.prune_cells(&self.cover, cells);
+            for row in pruned.iter() {
+                self.cover.remove_row(*row);
+            }
+
+            outcome = self.dance(seek, print, rand, tracer);
+
+            for row in pruned.iter().rev() {
+                self.cover.replace_row(*row);
+            }
+            self.post_dance(&ps);
+        }
+
+        self.restore(&eliminated);
+        outcome
+    }
+
+    // Load a pencil-mark grid into `prop_candidates`. Each whitespace separated word of
+    // `candidates` is a cell (top to bottom, then left to right), and lists the digits that are
+    // still candidates there; a `.` or `0` is a cell with no candidates left.
+    pub fn load_candidates(&mut self, candidates: &str) {
+        self.prop_candidates = candidates
+            .split_whitespace()
+            .map(|word| {
+                word.chars()
+                    .filter_map(|ch| ch.to_digit(10))
+                    .filter(|n| *n > 0)
+                    .fold(0, |mask, n| mask | 1 << (n - 1))
+            })
+            .collect();
+    }
+
+    // Remove the rows for every candidate missing from `candidates`, which has a bit mask for
+    // each cell like `with_allowed` (an empty slice removes nothing). Returns the removed rows,
+    // which `restore` will put back.
+    //
+    // Like `pre_dance`, this must be undone (by `restore`) only after everything done to the web
+    // since has been undone.
+    //
+    fn eliminate(&mut self, candidates: &[u64]) -> Vec<[usize; 3]> {
+        let mut eliminated = vec![];
+        for ([r, c], mask) in self.cells().into_iter().zip(candidates) {
+            for n in 1..=self.symbols() {
+                if mask & 1 << (n - 1) == 0 && self.row_is_live(r, c, n) {
+                    self.remove_row(r, c, n);
+                    eliminated.push([r, c, n]);
+                }
+            }
+        }
+        eliminated
+    }
+
+    fn restore(&mut self, eliminated: &[[usize; 3]]) {
+        for [r, c, n] in eliminated.iter().rev() {
+            self.replace_row(*r, *c, *n);
+        }
+    }
+
+    // Is the `[r, c, n]` row still in the web? Rows can be left out (see `with_allowed`), removed
+    // (see `eliminate`), or covered by some other row's column (as in `pre_dance`).
+    fn row_is_live(&self, r: usize, c: usize, n: usize) -> bool {
+        self.row_of(r, c, n)
+            .is_some_and(|row| self.cover.is_live(row))
+    }
+
+    fn remove_row(&mut self, r: usize, c: usize, n: usize) {
+        if let Some(row) = self.row_of(r, c, n) {
+            self.cover.remove_row(row);
+        }
+    }
+
+    fn replace_row(&mut self, r: usize, c: usize, n: usize) {
+        if let Some(row) = self.row_of(r, c, n) {
+            self.cover.replace_row(row);
+        }
+    }
+
+    fn pre_dance(&mut self, ps: &[[usize; 3]]) -> Knowing {
+        for (psi, id) in ps.iter().enumerate() {
+            let [r, c, n] = *id;
+            let selected = match self.row_of(r, c, n) {
+                Some(row) => self.admits(*id) && self.cover.select(row),
+                None => false,
+            };
+            if !selected {
+                self.post_dance(&ps[..psi]);
+                return Knowing::No;
+            }
+            self.board.set(r, c, n);
+        }
+        Knowing::Unknown
+    }
+
+    fn post_dance(&mut self, ps: &[[usize; 3]]) {
+        for [r, c, n] in ps.iter().rev() {
+            self.board.set(*r, *c, 0);
+            if let Some(row) = self.row_of(*r, *c, *n) {
+                self.cover.deselect(row);
+            }
+        }
+    }
+
+    // Search the cover for solutions, with the web as its `Hook`. The cover is set aside while it
+    // searches, so the hook can borrow the rest of the web.
+    fn dance(
+        &mut self,
+        seek: usize,
+        print: bool,
+        rand: bool,
+        tracer: Option<&mut Tracer>,
+    ) -> Outcome {
+        let mut cover = mem::replace(&mut self.cover, ExactCover::new(0, 0));
+        let budget = Budget::new(&self.limits);
+        let mut dancer = Dancer {
+            web: self,
+            seek,
+            print,
+            tracer,
+            budget,
+        };
+        cover.search(rand, &mut dancer);
+        let outcome = dancer.budget.outcome;
+        self.cover = cover;
+
+        if outcome.limit_reached() {
+            if self.solution_count < 2 {
+                self.uniq = Knowing::Unknown;
+            }
+        } else if self.solution_count == 1 && seek > 1 {
+            self.uniq = Knowing::Yes;
+        } else if self.solution_count == 0 {
+            self.possible = Knowing::No;
+        }
+        outcome
+    }
+
+    // Does every rule allow `id` to be placed next?
+    fn admits(&self, id: [usize; 3]) -> bool {
+        self.rules.iter().all(|rule| rule.admits(&self.board, id))
+    }
+
+    // The rows in `cover` the rules no longer admit in each of `cells` that is still empty (once
+    // each, so they can all be removed)
+    fn prune_cells(
+        &self,
+        cover: &ExactCover<[usize; 3]>,
+        mut cells: Vec<[usize; 2]>,
+    ) -> Vec<usize> {
+        let mut pruned = vec![];
+        if self.rules.is_empty() {
+            return pruned;
+        }
+        cells.sort();
+        cells.dedup();
+        for [wr, wc] in cells {
+            if wr < 1 || wc < 1 || wr > self.height() || wc > self.width() {
+                continue;
+            }
+            if !self.in_grid(wr, wc) || self.board.get(wr, wc) != 0 {
+                continue;
+            }
+            let column = self.indices_from_rcn(wr, wc, 1)[0];
+            for row in cover.rows_in(column) {
+                if !self.admits(*cover.label(row)) {
+                    pruned.push(row);
+                }
+            }
+        }
+        pruned
+    }
+
+    // `line` is the left end, the right end, where the line meets a box border, where it meets a
+    // cell border, and the line itself
+    fn fmt_horiz_line(
+        &self,
+        f: &mut dyn fmt::Write,
+        line: [char; 5],
+        sym_width: usize,
+    ) -> fmt::Result {
+        let [ls, rs, bm, tm, h] = line;
+        write!(f, "{}", ls)?;
+        for _ in 1..self.curtains {
+            for _ in 1..self.belts {
+                for _ in 0..sym_width {
+                    write!(f, "{}", h)?;
+                }
+                write!(f, "{}", tm)?;
+            }
+            for _ in 0..sym_width {
+                write!(f, "{}", h)?;
+            }
+            write!(f, "{}", bm)?;
+        }
+        for _ in 1..self.belts {
+            for _ in 0..sym_width {
+                write!(f, "{}", h)?;
+            }
+            write!(f, "{}", tm)?;
+        }
+        for _ in 0..sym_width {
+            write!(f, "{}", h)?;
+        }
+        writeln!(f, "{}", rs)
+    }
+
+    // Draw `sol` as a grid (see the `render` module for other ways to get at this)
+    pub fn fmt_solution(
+        &self,
+        f: &mut dyn fmt::Write,
+        sol: &[[usize; 3]],
+        charset: Charset,
+        colors: Option<&Colors>,
+    ) -> fmt::Result {
+        if self.grids.len() > 1 {
+            return self.fmt_board(f, sol, charset, colors);
+        }
+        let num = self.symbols();
+        let mut a = vec![vec!["".to_string(); num]; num];
+        let mut sym_width = 2;
+        for s in sol.iter() {
+            let [r, c, n] = s;
+            let n = n.to_string();
+
+            if sym_width < n.len() {
+                sym_width = n.len();
+            }
+
+            a[r - 1][c - 1] = n;
+        }
+
+        for (r_i, r) in a.iter().enumerate() {
+            if r_i == 0 {
+                self.fmt_horiz_line(f, charset.top(), sym_width)?;
+            } else if r_i % self.curtains == 0 {
+                self.fmt_horiz_line(f, charset.thick(), sym_width)?;
+            } else {
+                self.fmt_horiz_line(f, charset.thin(), sym_width)?;
+            }
+            for (c_i, c) in r.iter().enumerate() {
+                write!(f, "{}", charset.vert(c_i % self.belts == 0))?;
+                write!(f, "{}", paint(colors, r_i + 1, c_i + 1, c, sym_width))?;
+            }
+            writeln!(f, "{}", charset.vert(true))?;
+        }
+        // The bottom border
+        self.fmt_horiz_line(f, charset.bottom(), sym_width)
+    }
+
+    pub fn print_solution(&self, sol: &[[usize; 3]]) {
+        print!("{}", self.grid(sol).color(color_stdout()));
+    }
+
+    // Sets prop_solution to a subset of some random solution, generating a random sudoku puzzle.
+    //
+    // If a limit is reached before there is a solution, prop_solution is left empty. If one is
+    // reached while removing clues, the puzzle so far (which is unique) is kept, with more clues
+    // than it might have had. An error if the web has no solution at all.
+    //
+    pub fn random_puzzle(&mut self) -> Result<Outcome, String> {
+        self.prop_solution = vec![];
+        let outcome = self.random_solution();
+        if outcome.limit_reached() {
+            return Ok(outcome);
+        }
+        if self.possible != Knowing::Yes {
+            return Err("the web has no solution".to_string());
+        }
+        self.prop_solution = self.solution.clone();
+
+        rand::thread_rng().shuffle(&mut self.prop_solution);
+        for i in (0..self.prop_solution.len()).rev() {
+            let gone = self.prop_solution.remove(i);
+            let outcome = self.solve(2, false, false);
+            if outcome.limit_reached() {
+                self.prop_solution.push(gone);
+                return Ok(outcome);
+            }
+            if self.uniq == Knowing::No {
+                self.prop_solution.push(gone);
+            }
+        }
+        Ok(Outcome::Finished)
+    }
+
+    // Sets solution to a random solution of the puzzle in prop_solution.
+    //
+    // A random search that makes a bad choice early on can take a very long time to find out (for
+    // an anti-knight grid, often minutes), while one that doesn't is done in a few nodes more than
+    // there are cells. So the search runs out of nodes after a while and starts over with new
+    // random choices, allowing a number of nodes that follows the Luby sequence: it's never too
+    // patient for long, but it keeps trying more patient searches, in case the puzzle needs them.
+    // The web's own `limits` still apply to each search.
+    //
+    fn random_solution(&mut self) -> Outcome {
+        let limits = self.limits.clone();
+        let unit = 2 * self.possibilities() / self.symbols();
+        let mut attempt = 0;
+        let outcome = loop {
+            attempt += 1;
+            let patience = unit * luby(attempt);
+            self.limits.nodes = Some(limits.nodes.map_or(patience, |nodes| nodes.min(patience)));
+            let outcome = self.solve(1, false, true);
+            let impatient = limits.nodes.is_none_or(|nodes| nodes > patience);
+            if outcome != Outcome::OutOfNodes || !impatient {
+                break outcome;
+            }
+        };
+        self.limits = limits;
+        outcome
+    }
+
+    // One character for each of `self.cells()`
+    pub fn prop_solution_string(&self) -> String {
+        self.cells()
+            .iter()
+            .map(|[r, c]| {
+                self.prop_solution
+                    .iter()
+                    .find(|e| e[0] == *r && e[1] == *c)
+                    .map(|e| e[2].to_string())
+                    .unwrap_or_else(|| ".".to_string())
+            })
+            .collect()
+    }
+}
+
+// The Luby sequence (1, 1, 2, 1, 1, 2, 4, 1, 1, 2, 1, 1, 2, 4, 8, ...), counting from `i = 1`
+fn luby(i: usize) -> usize {
+    let mut k = 1;
+    while (1 << k) - 1 < i {
+        k += 1;
+    }
+    if i == (1 << k) - 1 {
+        1 << (k - 1)
+    } else {
+        luby(i + 1 - (1 << (k - 1)))
+    }
+}
+
+// The hook `dance` searches with. It keeps `board` and `prop_solution` up to date with the rows
+// chosen so far, asks the rules about every row, and counts the solutions.
+struct Dancer<'a, 't: 'a> {
+    web: &'a mut SudokuWeb,
+    seek: usize,
+    print: bool,
+    tracer: Option<&'a mut Tracer<'t>>,
+    budget: Budget,
+}
+
+impl<'a, 't> Hook<[usize; 3]> for Dancer<'a, 't> {
+    fn admits(&mut self, cover: &ExactCover<[usize; 3]>, row: usize) -> bool {
+        self.web.admits(*cover.label(row))
+    }
+
+    fn proceed(&mut self, _cover: &ExactCover<[usize; 3]>) -> bool {
+        self.budget.spend()
+    }
+
+    fn column(&mut self, _cover: &ExactCover<[usize; 3]>, column: usize, size: usize) {
+        if let Some(tracer) = self.tracer.as_mut() {
+            tracer.column(&self.web.column_name(column), column, size);
+        }
+    }
+
+    // Now that a row has been placed, remove the rows the rules no longer admit from every empty
+    // cell they watch from there, so that the sizes of those cells' columns stay honest.
+    fn chosen(&mut self, cover: &ExactCover<[usize; 3]>, row: usize) -> Vec<usize> {
+        let [r, c, n] = *cover.label(row);
+        if let Some(tracer) = self.tracer.as_mut() {
+            tracer.chosen([r, c, n]);
+        }
+        let web = &mut *self.web;
+        web.prop_solution.push([r, c, n]);
+        web.board.set(r, c, n);
+
+        let watched = web
+            .rules
+            .iter()
+            .flat_map(|rule| rule.watches(&web.board, r, c))
+            .collect();
+        web.prune_cells(cover, watched)
+    }
+
+    fn unchosen(&mut self, cover: &ExactCover<[usize; 3]>, row: usize) {
+        let [r, c, n] = *cover.label(row);
+        if let Some(tracer) = self.tracer.as_mut() {
+            tracer.unchosen([r, c, n]);
+        }
+        self.web.prop_solution.pop();
+        self.web.board.set(r, c, 0);
+    }
+
+    fn found(&mut self, _cover: &ExactCover<[usize; 3]>, _rows: &[usize]) -> bool {
+        if let Some(tracer) = self.tracer.as_mut() {
+            tracer.found();
+        }
+        let web = &mut *self.web;
+        web.possible = Knowing::Yes;
+        web.solution_count += 1;
+        web.solution = web.prop_solution.clone();
+        if self.print {
+            println!("[{}]: Solution found:", web.solution_count);
+            web.print_solution(&web.prop_solution);
+        }
+        if web.solution_count > 1 {
+            web.uniq = Knowing::No;
+        }
+        web.solution_count < self.seek
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grid(sol: &[[usize; 3]], nums: usize) -> Vec<Vec<usize>> {
+        let mut g = vec![vec![0; nums + 1]; nums + 1];
+        for [r, c, n] in sol.iter() {
+            g[*r][*c] = *n;
+        }
+        g
+    }
+
+    fn repeats_at(sol: &[[usize; 3]], nums: usize, offsets: &[(isize, isize)]) -> bool {
+        let g = grid(sol, nums);
+        (1..=nums).any(|r| {
+            (1..=nums).any(|c| {
+                offsets.iter().any(|(dr, dc)| {
+                    let (r2, c2) = (r as isize + dr, c as isize + dc);
+                    r2 >= 1
+                        && c2 >= 1
+                        && r2 <= nums as isize
+                        && c2 <= nums as isize
+                        && g[r][c] == g[r2 as usize][c2 as usize]
+                })
+            })
+        })
+    }
+
+    const KNIGHT: [(isize, isize); 4] = [(1, -2), (1, 2), (2, -1), (2, 1)];
+    const KING: [(isize, isize); 2] = [(1, -1), (1, 1)];
+
+    fn has_adjacent_consecutive(sol: &[[usize; 3]], nums: usize) -> bool {
+        let g = grid(sol, nums);
+        (1..=nums).any(|r| {
+            (1..=nums).any(|c| {
+                (r < nums && (g[r][c] as isize - g[r + 1][c] as isize).abs() == 1)
+                    || (c < nums && (g[r][c] as isize - g[r][c + 1] as isize).abs() == 1)
+            })
+        })
+    }
+
+    fn clues(s: &str, nums: usize) -> Vec<[usize; 3]> {
+        s.chars()
+            .enumerate()
+            .filter_map(|(i, ch)| {
+                ch.to_digit(10)
+                    .map(|n| [i / nums + 1, i % nums + 1, n as usize])
+            })
+            .collect()
+    }
+
+    // A 12 clue anti-knight puzzle, made by `random_puzzle` on `with_variants(3, 3,
+    // &[Variant::AntiKnight])`. It's only unique thanks to the anti-knight constraint.
+    const ANTI_KNIGHT: &str =
+        ".8..6................3.4..............91......2..8...............4...7...6......2";
+
+    #[test]
+    fn anti_knight_puzzle_is_unique() {
+        let mut sw = SudokuWeb::with_variants(3, 3, &[Variant::AntiKnight]);
+        sw.prop_solution = clues(ANTI_KNIGHT, 9);
+        sw.solve(2, false, false);
+        assert_eq!(sw.uniq, Knowing::Yes);
+        assert_eq!(sw.solution.len(), 81);
+        assert!(!repeats_at(&sw.solution, 9, &KNIGHT));
+
+        let mut sw = SudokuWeb::new(3, 3);
+        sw.prop_solution = clues(ANTI_KNIGHT, 9);
+        sw.solve(2, false, false);
+        assert_eq!(sw.uniq, Knowing::No);
+    }
+
+    #[test]
+    fn anti_king_solutions_have_no_king_repeats() {
+        let mut sw = SudokuWeb::with_variants(3, 3, &[Variant::AntiKing]);
+        sw.solve(1, false, true);
+        assert_eq!(sw.possible, Knowing::Yes);
+        assert!(!repeats_at(&sw.solution, 9, &KING));
+    }
+
+    #[test]
+    fn anti_knight_clues_a_knights_move_apart_conflict() {
+        let mut sw = SudokuWeb::with_variants(3, 3, &[Variant::AntiKnight]);
+        sw.prop_solution = vec![[1, 3, 5], [2, 5, 5]];
+        sw.solve(1, false, false);
+        assert_eq!(sw.possible, Knowing::No);
+
+        // The web is left intact for the next solve
+        sw.prop_solution = clues(ANTI_KNIGHT, 9);
+        sw.solve(2, false, false);
+        assert_eq!(sw.uniq, Knowing::Yes);
+    }
+
+    #[test]
+    fn generated_anti_knight_puzzles_are_unique() {
+        let mut sw = SudokuWeb::with_variants(3, 3, &[Variant::AntiKnight, Variant::AntiKing]);
+        sw.random_puzzle().unwrap();
+        sw.solve(2, false, false);
+        assert_eq!(sw.uniq, Knowing::Yes);
+        assert!(!repeats_at(&sw.solution, 9, &KNIGHT));
+        assert!(!repeats_at(&sw.solution, 9, &KING));
+    }
+
+    // Mitchell Lee's "Miracle Sudoku": anti-knight, anti-king, and non-consecutive, with only a
+    // 1 in r5c3 and a 2 in r6c7 given.
+    #[test]
+    fn miracle_sudoku_is_unique() {
+        let variants = [
+            Variant::AntiKnight,
+            Variant::AntiKing,
+            Variant::NonConsecutive,
+        ];
+        let mut sw = SudokuWeb::with_variants(3, 3, &variants);
+        sw.prop_solution = vec![[5, 3, 1], [6, 7, 2]];
+        sw.solve(2, false, false);
+        assert_eq!(sw.uniq, Knowing::Yes);
+        assert!(!repeats_at(&sw.solution, 9, &KNIGHT));
+        assert!(!repeats_at(&sw.solution, 9, &KING));
+        assert!(!has_adjacent_consecutive(&sw.solution, 9));
+    }
+
+    #[test]
+    fn generated_non_consecutive_puzzles_are_unique() {
+        let mut sw = SudokuWeb::with_variants(2, 3, &[Variant::NonConsecutive]);
+        sw.random_puzzle().unwrap();
+        sw.solve(2, false, false);
+        assert_eq!(sw.uniq, Knowing::Yes);
+        assert!(!has_adjacent_consecutive(&sw.solution, 6));
+    }
+
+    #[test]
+    fn samurai_solutions_are_valid_in_every_grid() {
+        let mut sw = SudokuWeb::samurai(3, 3);
+        assert_eq!(sw.cells().len(), 369);
+        sw.solve(1, false, false);
+        assert_eq!(sw.solution.len(), 369);
+
+        let mut board = vec![vec![0; 22]; 22];
+        for [r, c, n] in sw.solution.iter() {
+            board[*r][*c] = *n;
+        }
+        for [ro, co] in sw.grids.iter() {
+            let sol: Vec<[usize; 3]> = (1..=9)
+                .flat_map(|r| (1..=9).map(move |c| [r, c]))
+                .map(|[r, c]| [r, c, board[ro + r][co + c]])
+                .collect();
+            let mut single = SudokuWeb::new(3, 3);
+            single.prop_solution = sol;
+            single.solve(1, false, false);
+            assert_eq!(single.possible, Knowing::Yes);
+        }
+    }
+
+    #[test]
+    fn generated_samurai_puzzles_are_unique() {
+        let mut sw = SudokuWeb::samurai(2, 3);
+        sw.random_puzzle().unwrap();
+        assert!(sw.prop_solution.len() < sw.cells().len());
+        sw.solve(2, false, false);
+        assert_eq!(sw.uniq, Knowing::Yes);
+        assert_eq!(sw.solution.len(), sw.cells().len());
+    }
+
+    #[test]
+    fn shared_puzzles_are_unique_in_every_geometry() {
+        let mut webs = [SudokuWeb::new(2, 3), SudokuWeb::new(3, 2)];
+        let clues = shared::random_shared_puzzle(&mut webs).unwrap();
+        assert_eq!(
+            shared::check_shared(&mut webs, &clues),
+            shared::Shared::Unique
+        );
+
+        // Boxes are 3x2 in the first web, but 2x3 in the second
+        webs[0].prop_solution = vec![];
+        webs[0].solve(1, false, false);
+        let grid = webs[0].solution.clone();
+        assert_eq!(
+            shared::check_shared(&mut webs, &grid),
+            shared::Shared::Impossible(1)
+        );
+        assert_eq!(
+            shared::check_shared(&mut webs, &[]),
+            shared::Shared::NotUnique(0)
+        );
+
+        // Only the second web says which cells are even and which odd
+        let parities = format!("eeeooooooeee{}", ".".repeat(24));
+        let mut webs = [SudokuWeb::new(2, 3), SudokuWeb::parity(3, 2, &parities)];
+        let clues = shared::random_shared_puzzle(&mut webs).unwrap();
+        assert_eq!(
+            shared::check_shared(&mut webs, &clues),
+            shared::Shared::Unique
+        );
+        for [r, c, n] in webs[0].solution.iter().filter(|id| id[0] <= 2) {
+            assert_eq!(n % 2 == 0, (*r == 1) == (*c <= 3));
+        }
+    }
+
+    #[test]
+    fn parity_cells_only_hold_their_parity() {
+        // The first row is 4 even digits, then 5 odd ones
+        let puzzle = format!("eeeeooooo{}", ".".repeat(72));
+        let mut sw = SudokuWeb::parity(3, 3, &puzzle);
+        sw.solve(1, false, false);
+        assert_eq!(sw.possible, Knowing::Yes);
+        for [r, c, n] in sw.solution.iter().filter(|id| id[0] == 1) {
+            assert_eq!(n % 2 == 0, *c <= 4, "r{}c{} has the wrong parity", r, c);
+        }
+
+        // A given that breaks its cell's parity is a conflict
+        let mut sw = SudokuWeb::parity(3, 3, &format!("3{}", &puzzle[1..]));
+        sw.solve(1, false, false);
+        assert_eq!(sw.possible, Knowing::No);
+
+        // `0`, and digits past the last symbol, are empty cells
+        let mut sw = SudokuWeb::parity(2, 2, "0e..5o..........");
+        assert!(sw.prop_solution.is_empty());
+        sw.solve(1, false, false);
+        assert_eq!(sw.possible, Knowing::Yes);
+    }
+
+    #[test]
+    fn webs_can_have_more_than_64_symbols() {
+        // A full 81x81 grid, with boxes 9 cells tall and 9 wide, less a few cells
+        let mut sw = SudokuWeb::new(9, 9);
+        let full: Vec<[usize; 3]> = (0..81)
+            .flat_map(|r| (0..81).map(move |c| [r + 1, c + 1, (9 * (r % 9) + r / 9 + c) % 81 + 1]))
+            .collect();
+        let gone = [[1, 1], [1, 80], [40, 2], [81, 81]];
+        sw.prop_solution = full
+            .iter()
+            .filter(|[r, c, _]| !gone.contains(&[*r, *c]))
+            .copied()
+            .collect();
+        sw.solve(2, false, false);
+        assert_eq!(sw.uniq, Knowing::Yes);
+        let mut solution = sw.solution.clone();
+        solution.sort();
+        assert_eq!(solution, full);
+        assert!(sw.allows(1, 1, 81));
+    }
+
+    #[test]
+    fn eliminated_candidates_are_restored_after_solving() {
+        let mut sw = SudokuWeb::new(2, 2);
+        sw.solve(1000, false, false);
+        assert_eq!(sw.solution_count, 288);
+
+        // r1c1 is 1 or 2, and r2c1 is 3 or 4
+        sw.load_candidates(&format!("12 1234 1234 1234 34{}", " 1234".repeat(11)));
+        sw.solve(1000, false, false);
+        assert_eq!(sw.solution_count, 96);
+
+        // A given that is no longer a candidate is a conflict
+        sw.prop_solution = vec![[1, 1, 3]];
+        sw.solve(1000, false, false);
+        assert_eq!(sw.possible, Knowing::No);
+
+        sw.prop_candidates = vec![];
+        sw.prop_solution = vec![];
+        sw.solve(1000, false, false);
+        assert_eq!(sw.solution_count, 288);
+    }
+
+    #[test]
+    fn negative_white_dots_match_the_non_consecutive_variant() {
+        let mut variant = SudokuWeb::with_variants(2, 3, &[Variant::NonConsecutive]);
+        variant.prop_solution = vec![[1, 1, 1]];
+        variant.solve(1000, false, false);
+
+        let mut rule = SudokuWeb::new(2, 3);
+        let relations = relations::Relations::parse("negative w", 6, 6).unwrap();
+        rule.rules.push(Box::new(relations));
+        rule.prop_solution = vec![[1, 1, 1]];
+        rule.solve(1000, false, false);
+
+        assert!(variant.solution_count > 0);
+        assert_eq!(rule.solution_count, variant.solution_count);
+
+        // Only a white dot lets two neighbors be consecutive; a greater-than sign doesn't
+        let solve = |text: &str, backend, clues| {
+            let mut sw = SudokuWeb::new(3, 3);
+            sw.backend = backend;
+            let relations = relations::Relations::parse(text, 9, 9).unwrap();
+            sw.rules.push(Box::new(relations));
+            sw.prop_solution = clues;
+            sw.solve(1, false, false);
+            (sw.possible, sw.solution)
+        };
+        let dotted = "negative w\nr1c1 w r1c2";
+        let (possible, solution) = solve(dotted, Backend::Dance, vec![[1, 1, 1], [1, 2, 2]]);
+        assert_eq!(possible, Knowing::Yes);
+        assert_eq!(solve(dotted, Backend::Propagate, solution).0, Knowing::Yes);
+        for backend in [Backend::Dance, Backend::Propagate] {
+            let signed = solve(
+                "negative w\nr1c1 < r1c2",
+                backend,
+                vec![[1, 1, 1], [1, 2, 2]],
+            );
+            assert_eq!(signed.0, Knowing::No);
+        }
+    }
+
+    #[test]
+    fn marked_neighbors_hold_their_relation() {
+        let text = "r1c1 x r1c2\nr1c2 > r2c2  # greater-than\nr1c3 b r1c4\nr2c1 w r3c1\n";
+        let mut sw = SudokuWeb::new(3, 3);
+        sw.rules
+            .push(Box::new(relations::Relations::parse(text, 9, 9).unwrap()));
+        sw.solve(1, false, false);
+        assert_eq!(sw.possible, Knowing::Yes);
+
+        let g = grid(&sw.solution, 9);
+        assert_eq!(g[1][1] + g[1][2], 10);
+        assert!(g[1][2] > g[2][2]);
+        assert!(g[1][3] == 2 * g[1][4] || g[1][4] == 2 * g[1][3]);
+        assert!(g[2][1] + 1 == g[3][1] || g[3][1] + 1 == g[2][1]);
+
+        let bad = relations::Relations::parse("r1c1 < r1c2\nr1c1 x r1c3", 9, 9);
+        assert_eq!(
+            bad.unwrap_err(),
+            "line 2: the cells are not neighbors: r1c1 x r1c3"
+        );
+        let bad = relations::Relations::parse("r9c1 < r10c1", 9, 9);
+        assert_eq!(
+            bad.unwrap_err(),
+            "line 1: the cells must be on the board: r9c1 < r10c1"
+        );
+    }
+
+    // Every full 4x4 grid, by brute force
+    fn all_4x4_grids() -> Vec<Vec<[usize; 3]>> {
+        fn fill(sol: &mut Vec<[usize; 3]>, grids: &mut Vec<Vec<[usize; 3]>>) {
+            if sol.len() == 16 {
+                grids.push(sol.clone());
+                return;
+            }
+            let (r, c) = (sol.len() / 4 + 1, sol.len() % 4 + 1);
+            for n in 1..=4 {
+                let clash = sol.iter().any(|[r2, c2, n2]| {
+                    *n2 == n
+                        && (*r2 == r
+                            || *c2 == c
+                            || ((r2 - 1) / 2, (c2 - 1) / 2) == ((r - 1) / 2, (c - 1) / 2))
+                });
+                if !clash {
+                    sol.push([r, c, n]);
+                    fill(sol, grids);
+                    sol.pop();
+                }
+            }
+        }
+        let mut grids = vec![];
+        fill(&mut vec![], &mut grids);
+        grids
+    }
+
+    #[test]
+    fn lines_allow_exactly_the_grids_that_satisfy_them() {
+        let text = "thermo r1c1 r2c2\narrow r4c4 r3c4 r4c3\nsandwich row 2 0\n";
+        let lines = lines::Lines::parse(text, 4, 4).unwrap();
+        let grids = all_4x4_grids();
+        assert_eq!(grids.len(), 288);
+        let satisfying = grids
+            .iter()
+            .filter(|sol| lines.verify(&Board::with_solution(4, 4, 4, sol)).is_ok())
+            .count();
+
+        let mut sw = SudokuWeb::new(2, 2);
+        sw.rules.push(Box::new(lines));
+        sw.solve(1000, false, false);
+        assert!(satisfying > 1);
+        assert_eq!(sw.solution_count, satisfying);
+    }
+
+    #[test]
+    fn broken_lines_are_reported() {
+        let lines = lines::Lines::parse(
+            "arrow r1c1 r1c2 r2c1 # a 2-cell arrow\nthermo r2c1 r2c2",
+            4,
+            4,
+        )
+        .unwrap();
+        let mut board = Board::with_solution(4, 4, 4, &all_4x4_grids()[0]);
+        assert_eq!(
+            lines.verify(&board),
+            Err("broken: arrow r1c1 r1c2 r2c1".to_string())
+        );
+        board.set(1, 1, 0);
+        assert_eq!(
+            lines.verify(&board),
+            Err("not filled in: arrow r1c1 r1c2 r2c1".to_string())
+        );
+
+        let bad = lines::Lines::parse("thermo r1c1 r3c1", 4, 4);
+        assert_eq!(
+            bad.unwrap_err(),
+            "line 1: each cell of a path must touch the one before it: thermo r1c1 r3c1"
+        );
+        let bad = lines::Lines::parse("killer 9 r4c4 r4c5", 4, 4);
+        assert_eq!(
+            bad.unwrap_err(),
+            "line 1: the cells must be on the board: killer 9 r4c4 r4c5"
+        );
+        let bad = lines::Lines::parse("sandwich col 5 0", 4, 4);
+        assert_eq!(
+            bad.unwrap_err(),
+            "line 1: the line must be on the board: sandwich col 5 0"
+        );
+    }
+
+    #[test]
+    fn exact_cover_finds_every_eight_queens_solution() {
+        // A column for each rank and file, and a secondary column for each diagonal
+        let n = 8;
+        let mut queens = ExactCover::new(2 * n, 2 * (2 * n - 1));
+        for r in 0..n {
+            for c in 0..n {
+                let diagonals = [2 * n + r + c, 2 * n + (2 * n - 1) + (n - 1 + r - c)];
+                queens.add_row([r, c], &[r, n + c, diagonals[0], diagonals[1]]);
+            }
+        }
+
+        let solutions = queens.solutions(1000);
+        assert_eq!(solutions.len(), 92);
+        for solution in solutions.iter() {
+            for (i, [r, c]) in solution.iter().enumerate() {
+                for [r2, c2] in solution[i + 1..].iter() {
+                    assert!(r != r2 && c != c2 && r + c2 != r2 + c && r + c != r2 + c2);
+                }
+            }
+        }
+        assert_eq!(queens.solutions(3).len(), 3);
+    }
+
+    #[test]
+    fn exact_cover_rejects_rows_without_columns_or_with_bad_ones() {
+        let bad: [&[usize]; 4] = [&[], &[3], &[0, 5], &[1, 2, 1]];
+        for columns in bad.iter() {
+            let added = std::panic::catch_unwind(|| {
+                let mut cover = ExactCover::new(2, 1);
+                cover.add_row((), columns);
+            });
+            assert!(added.is_err(), "a row in {:?} was added", columns);
+        }
+
+        let mut cover = ExactCover::new(2, 1);
+        cover.add_row((), &[0, 2]);
+        cover.add_row((), &[1]);
+        assert_eq!(cover.solutions(2), vec![vec![(), ()]]);
+    }
+
+    #[test]
+    fn secondary_columns_are_covered_at_most_once_and_never_chosen() {
+        // The columns `search` chose, and the labels of each solution it found
+        #[derive(Default)]
+        struct Record(Vec<usize>, Vec<Vec<char>>);
+        impl Hook<char> for Record {
+            fn column(&mut self, _cover: &ExactCover<char>, column: usize, _size: usize) {
+                self.0.push(column);
+            }
+
+            fn found(&mut self, cover: &ExactCover<char>, rows: &[usize]) -> bool {
+                let mut labels: Vec<char> = rows.iter().map(|row| *cover.label(*row)).collect();
+                labels.sort_unstable();
+                self.1.push(labels);
+                true
+            }
+        }
+
+        // Two primary columns, and a secondary column that `a` and `b` share
+        let mut cover = ExactCover::new(2, 1);
+        cover.add_row('a', &[0, 2]);
+        cover.add_row('b', &[1, 2]);
+        cover.add_row('c', &[0]);
+        cover.add_row('d', &[1]);
+        for rand in [false, true] {
+            let mut record = Record::default();
+            cover.search(rand, &mut record);
+            assert!(!record.0.is_empty() && record.0.iter().all(|column| *column < 2));
+            record.1.sort();
+            assert_eq!(
+                record.1,
+                vec![vec!['a', 'd'], vec!['b', 'c'], vec!['c', 'd']]
+            );
+        }
+    }
+
+    #[test]
+    fn dimacs_models_decode_to_checked_grids() {
+        let mut sw = SudokuWeb::new(2, 2);
+        sw.prop_solution = vec![[1, 1, 1], [2, 3, 1]];
+        let cnf = sw.to_dimacs();
+        let header = cnf.lines().find(|l| l.starts_with("p cnf")).unwrap();
+        let clauses = cnf.lines().filter(|l| l.ends_with(" 0")).count();
+        assert_eq!(header, format!("p cnf 64 {}", clauses));
+
+        sw.solve(1, false, false);
+        let literal = |id: &[usize; 3]| {
+            let variable = sw.possibility_index(id[0], id[1], id[2]) + 1;
+            if sw.solution.contains(id) {
+                format!("{}", variable)
+            } else {
+                format!("-{}", variable)
+            }
+        };
+        let ids: Vec<[usize; 3]> = sw
+            .cells()
+            .iter()
+            .flat_map(|[r, c]| (1..=4).map(move |n| [*r, *c, n]))
+            .collect();
+        let model: Vec<String> = ids.iter().map(literal).collect();
+        let model = format!("s SATISFIABLE\nv {} 0\n", model.join(" "));
+        let mut decoded = sw.read_model(&model).unwrap();
+        decoded.sort();
+        let mut expected = sw.solution.clone();
+        expected.sort();
+        assert_eq!(decoded, expected);
+
+        // r1c1 changed from 1 to 2
+        let changed = model.replace("v 1 -2 ", "v -1 2 ");
+        assert!(sw.read_model(&changed).is_err());
+        assert_eq!(sw.read_model("UNSAT\n"), Err("unsatisfiable".to_string()));
+    }
+
+    #[test]
+    fn dancing_and_propagating_agree_on_rules_and_killer_cages() {
+        let mut variant = SudokuWeb::with_variants(2, 3, &[Variant::NonConsecutive]);
+        variant.prop_solution = vec![[1, 1, 1]];
+        variant.solve(1000, false, false);
+        assert!(variant.solution_count > 0);
+
+        for backend in [Backend::Dance, Backend::Propagate] {
+            let mut dots = SudokuWeb::new(2, 3);
+            let relations = relations::Relations::parse("negative w", 6, 6).unwrap();
+            dots.rules.push(Box::new(relations));
+            dots.prop_solution = vec![[1, 1, 1]];
+            dots.backend = backend;
+            dots.solve(1000, false, false);
+            assert_eq!(dots.solution_count, variant.solution_count);
+        }
+
+        // With a killer cage as well as the other shapes
+        let text = "thermo r1c1 r2c2\narrow r4c4 r3c4 r4c3\nsandwich row 2 0\nkiller 5 r1c2 r1c3\n";
+        let lines = lines::Lines::parse(text, 4, 4).unwrap();
+        let satisfying = all_4x4_grids()
+            .iter()
+            .filter(|sol| lines.verify(&Board::with_solution(4, 4, 4, sol)).is_ok())
+            .count();
+        assert!(satisfying > 1);
+
+        for backend in [Backend::Dance, Backend::Propagate] {
+            let mut sw = SudokuWeb::new(2, 2);
+            sw.rules.push(Box::new(lines.clone()));
+            sw.backend = backend;
+            sw.solve(1000, false, false);
+            assert_eq!(sw.solution_count, satisfying);
+            assert!(sw.check_solution(&sw.solution).is_ok());
+        }
+    }
+
+    #[test]
+    fn matrix_exports_show_what_the_clues_leave() {
+        let mut sw = SudokuWeb::new(2, 2);
+        sw.prop_solution = vec![[1, 1, 1]];
+        let text = sw.matrix_text();
+        let columns: Vec<&str> = text.lines().filter(|l| l.starts_with("column ")).collect();
+        let rows: Vec<&str> = text.lines().filter(|l| l.starts_with("row ")).collect();
+        // The clue covers 4 columns, and takes 11 other rows out with its own
+        assert_eq!((columns.len(), rows.len()), (60, 53));
+        assert!(columns.contains(&"column 1 r1c2 3"));
+        assert!(columns.contains(&"column 63 b4#4 4"));
+        assert!(rows.contains(&"row r1c2=2: 1 17 37 49"));
+
+        let bitmap = sw.matrix_bitmap();
+        assert!(bitmap.width() > 60 * 8 && bitmap.height() > 53 * 8);
+        let pbm = bitmap.to_pbm();
+        let header = format!("P4\n{} {}\n", bitmap.width(), bitmap.height());
+        assert!(pbm.starts_with(header.as_bytes()));
+        assert_eq!(
+            pbm.len(),
+            header.len() + bitmap.width().div_ceil(8) * bitmap.height()
+        );
+        assert!(bitmap.to_png().starts_with(b"\x89PNG\r\n\x1a\n"));
+
+        // Everything is put back afterwards
+        sw.solve(2, false, false);
+        assert_eq!(sw.uniq, Knowing::No);
+        assert_eq!(sw.matrix_text(), text);
+    }
+
+    #[test]
+    fn traced_solves_account_for_every_step() {
+        let mut sw = SudokuWeb::new(2, 2);
+        sw.prop_solution = vec![[1, 1, 1], [2, 3, 1], [3, 2, 2]];
+        let mut out: Vec<u8> = vec![];
+        let stats = sw.solve_traced(5, false, false, Some(&mut out));
+        let trace = String::from_utf8(out).unwrap();
+        let events = |kind: &str| {
+            let tag = format!(r#"{{"event":"{}","#, kind);
+            trace.lines().filter(|l| l.starts_with(&tag)).count()
+        };
+
+        assert_eq!(stats.solutions, sw.solution_count);
+        assert_eq!(events("solution"), sw.solution_count);
+        assert_eq!(events("choose"), stats.nodes());
+        assert_eq!(events("backtrack"), stats.backtracks());
+        // Every row chosen is unchosen again on the way out
+        assert_eq!(stats.nodes(), stats.backtracks());
+        assert_eq!(
+            events("column"),
+            stats.levels.iter().map(|l| l.columns).sum()
+        );
+        assert!(trace
+            .lines()
+            .last()
+            .unwrap()
+            .starts_with(r#"{"event":"done","#));
+        assert_eq!(stats.levels[0].columns, 1);
+
+        let solution = sw.solution.clone();
+        sw.solve(5, false, false);
+        assert_eq!(sw.solution, solution);
+        assert_eq!(sw.solution_count, stats.solutions);
+    }
+
+    #[test]
+    fn limited_solves_stop_and_leave_the_web_usable() {
+        // The 17 clue puzzle from `main`
+        let v17 =
+            "...8.1..........435............7.8........1...2..3....6......75..34........2..6..";
+        let mut sw = SudokuWeb::new(3, 3);
+        for backend in [Backend::Dance, Backend::Propagate] {
+            sw.backend = backend;
+            sw.prop_solution = vec![];
+            sw.limits.nodes = Some(100);
+            assert_eq!(sw.solve(1000, false, false), Outcome::OutOfNodes);
+            assert!(sw.solution_count < 1000);
+
+            let token = limits::CancelToken::new();
+            sw.limits = Limits {
+                cancel: Some(token.clone()),
+                ..Limits::default()
+            };
+            token.cancel();
+            assert_eq!(sw.solve(2, false, false), Outcome::Cancelled);
+            assert_eq!(sw.uniq, Knowing::Unknown);
+
+            sw.limits = Limits {
+                deadline: Some(std::time::Instant::now()),
+                ..Limits::default()
+            };
+            assert_eq!(sw.solve(2, false, false), Outcome::OutOfTime);
+
+            sw.limits = Limits::default();
+            sw.prop_solution = clues(v17, 9);
+            assert_eq!(sw.solve(2, false, false), Outcome::Finished);
+            assert_eq!(sw.uniq, Knowing::Yes);
+            assert!(sw.check_solution(&sw.solution).is_ok());
+        }
+
+        sw.limits.nodes = Some(5);
+        assert_eq!(sw.random_puzzle(), Ok(Outcome::OutOfNodes));
+        assert!(sw.prop_solution.is_empty());
+
+        // A web with no solution has no puzzle either
+        let mut sw = SudokuWeb::new(2, 2);
+        let lines = lines::Lines::parse("killer 2 r1c1 r1c2", 4, 4).unwrap();
+        sw.rules.push(Box::new(lines));
+        assert_eq!(
+            sw.random_puzzle(),
+            Err("the web has no solution".to_string())
+        );
+        assert!(sw.prop_solution.is_empty());
+        assert!(sw.generate().is_none());
+    }
+
+    #[test]
+    fn svg_draws_every_edge_digit_mark_and_cage() {
+        let mut sw = SudokuWeb::new(2, 2);
+        let killer = lines::Lines::parse("killer 7 r1c1 r1c2 r2c1", 4, 4).unwrap();
+        sw.rules.push(Box::new(killer));
+        sw.prop_solution = vec![[1, 1, 1], [2, 3, 1]];
+        sw.solve(1, false, false);
+        let svg = sw.to_svg(&sw.solution, &[]);
+        let count = |s: &str, pattern: &str| s.matches(pattern).count();
+        assert_eq!(count(&svg, r#"class="given""#), 2);
+        assert_eq!(count(&svg, r#"class="solved""#), 14);
+        assert_eq!(count(&svg, r#"class="total">7<"#), 1);
+        // 5 lines across and 5 down, each 4 cells long; 3 of each are thick
+        let thin = svg.lines().find(|l| l.contains("#999")).unwrap();
+        let thick = svg
+            .lines()
+            .find(|l| l.contains("stroke-width=\"3\""))
+            .unwrap();
+        assert_eq!((count(thin, "h40"), count(thin, "v40")), (8, 8));
+        assert_eq!((count(thick, "h40"), count(thick, "v40")), (12, 12));
+        // The L-shaped cage has six sides, drawn as a segment along each of the 8 cell edges on its
+        // outside
+        let cage = svg.lines().find(|l| l.contains("dasharray")).unwrap();
+        assert_eq!(count(cage, "M"), 8);
+
+        sw.prop_candidates = vec![];
+        let marks = vec![0b1010; 16];
+        let svg = sw.to_svg(&[], &marks);
+        assert_eq!(count(&svg, r#"class="mark""#), 14 * 2);
+
+        let samurai = SudokuWeb::samurai(3, 3);
+        let svg = samurai.to_svg(&[], &[]);
+        assert!(svg.ends_with("</svg>\n"));
+    }
+
+    #[test]
+    fn booklets_group_puzzles_and_point_at_every_object() {
+        let mut sw = SudokuWeb::new(2, 2);
+        let puzzles: Vec<grading::Puzzle> = (0..5).map(|_| sw.generate().unwrap()).collect();
+        for puzzle in puzzles.iter() {
+            sw.prop_solution = puzzle.clues.clone();
+            assert_eq!(sw.grade(), Some(puzzle.difficulty));
+        }
+        // A clue that contradicts the rest leaves nothing to grade, whatever was graded before
+        sw.prop_solution = clues("12.43.1221.343.1", 4);
+        assert_eq!(sw.grade(), Some(grading::Difficulty::Easy));
+        sw.prop_solution.push([1, 3, 1]);
+        assert_eq!(sw.grade(), None);
+        let layout = pdf::Booklet {
+            title: "Shidoku (4x4)".to_string(),
+            per_page: 2,
+            answers_per_page: 4,
+            page: pdf::LETTER,
+        };
+        let pdf = String::from_utf8(sw.booklet(&puzzles, &layout)).unwrap();
+        assert!(pdf.starts_with("%PDF-1.4\n") && pdf.ends_with("%%EOF\n"));
+
+        let pages: usize = grading::Difficulty::ALL
+            .iter()
+            .map(|d| {
+                puzzles
+                    .iter()
+                    .filter(|p| p.difficulty == *d)
+                    .count()
+                    .div_ceil(2)
+            })
+            .sum::<usize>()
+            + 2;
+        assert!(pdf.contains(&format!("/Count {} >>", pages)));
+        assert!(pdf.contains(&format!("({} / {}) Tj", pages, pages)));
+        assert!(pdf.contains("(Shidoku \\(4x4\\)) Tj"));
+
+        // Every object is where the cross-reference table says
+        let xref = pdf.rfind("\nxref\n").unwrap() + 1;
+        let offsets = pdf[xref..]
+            .lines()
+            .skip(3)
+            .take_while(|l| l.ends_with(" n "));
+        for (i, line) in offsets.enumerate() {
+            let offset: usize = line[..10].parse().unwrap();
+            assert!(pdf[offset..].starts_with(&format!("{} 0 obj", i + 1)));
+        }
+        assert!(pdf.contains(&format!("startxref\n{}\n", xref)));
+    }
+
+    #[test]
+    fn rasters_highlight_cells_and_color_digits() {
+        let mut sw = SudokuWeb::new(2, 2);
+        sw.prop_solution = vec![[1, 1, 1], [2, 3, 1]];
+        sw.solve(1, false, false);
+        let style = raster::RasterStyle::default();
+        let bitmap = sw.to_bitmap(&sw.solution, &style, &[[4, 4]]);
+        assert_eq!(
+            (bitmap.width(), bitmap.height()),
+            (4 * 32 + 16, 4 * 32 + 16)
+        );
+        let colors_in = |r: usize, c: usize| {
+            let mut colors = vec![];
+            for y in (8 + (r - 1) * 32 + 2)..(8 + r * 32 - 2) {
+                for x in (8 + (c - 1) * 32 + 2)..(8 + c * 32 - 2) {
+                    if !colors.contains(&bitmap.get(x, y)) {
+                        colors.push(bitmap.get(x, y));
+                    }
+                }
+            }
+            colors.sort();
+            colors
+        };
+        assert_eq!(colors_in(1, 1), vec![bitmap::BLACK, bitmap::WHITE]);
+        assert_eq!(colors_in(1, 2), vec![style.solved, bitmap::WHITE]);
+        let mut highlighted = vec![style.solved, style.highlight];
+        highlighted.sort();
+        assert_eq!(colors_in(4, 4), highlighted);
+        assert!(sw
+            .to_png(&[], &style, &[])
+            .starts_with(b"\x89PNG\r\n\x1a\n"));
+    }
+
+    #[test]
+    fn grids_render_to_strings_and_writers_in_either_charset() {
+        let mut sw = SudokuWeb::new(2, 2);
+        sw.prop_solution = clues("12..34..........", 4);
+        let ascii = "\
++==+==+==+==+
+| 1: 2|  :  |
++--+--+--+--+
+| 3: 4|  :  |
++==+==+==+==+
+|  :  |  :  |
++--+--+--+--+
+|  :  |  :  |
++==+==+==+==+
+";
+        assert_eq!(sw.grid(&sw.prop_solution).ascii().to_string(), ascii);
+        let unicode = sw.to_string();
+        assert!(unicode.starts_with("╔══╤══╦══╤══╗\n║ 1│ 2║  │  ║\n╟──┼──╫──┼──╢\n"));
+
+        let mut out: Vec<u8> = vec![];
+        sw.write_solution(&mut out, &sw.prop_solution, render::Charset::Ascii)
+            .unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), ascii);
+
+        let samurai = SudokuWeb::samurai(3, 3);
+        let board = samurai.grid(&[[1, 1, 5]]).ascii().to_string();
+        assert!(board.is_ascii() && board.contains("| 5:"));
+        assert_eq!(board.lines().count(), 2 * samurai.height() + 1);
+    }
+
+    #[test]
+    fn colored_grids_pick_out_givens_and_conflicts() {
+        let mut sw = SudokuWeb::new(2, 2);
+        sw.prop_solution = clues("12..34..........", 4);
+        // r1c3 is solved right, r2c3 repeats r2c2's 4, and r4c1 repeats the 3 above it
+        let mut sol = sw.prop_solution.clone();
+        sol.extend([[1, 3, 3], [2, 3, 4], [4, 1, 3]]);
+        assert_eq!(sw.conflicts(&sol), vec![[2, 1], [2, 2], [2, 3], [4, 1]]);
+
+        let colored = sw.grid(&sol).ascii().color(true).to_string();
+        let first = colored.lines().nth(1).unwrap();
+        assert!(first.starts_with("| \x1b[1m1\x1b[0m: \x1b[1m2\x1b[0m| \x1b[36m3"));
+        assert!(colored.contains("| \x1b[1;31m3\x1b[0m: \x1b[1;31m4\x1b[0m| \x1b[31m4"));
+        let plain = sw.grid(&sol).ascii().to_string();
+        assert!(!plain.contains('\x1b'));
+        let mut stripped = colored.clone();
+        for code in ["1;31", "31", "1", "36", "0"] {
+            stripped = stripped.replace(&format!("\x1b[{}m", code), "");
+        }
+        assert_eq!(stripped, plain);
+    }
+
+    #[test]
+    fn pencil_marks_show_the_candidates_left() {
+        let mut sw = SudokuWeb::new(2, 2);
+        sw.prop_solution = clues("12..34..........", 4);
+        let marks = sw.candidates().unwrap();
+        assert_eq!(marks.len(), 16);
+        // r1c1 holds its clue, r1c3 can take what row 1 lacks, and r3c1 what column 1 lacks
+        assert_eq!((marks[0], marks[2], marks[8]), (0b0001, 0b1100, 0b1010));
+
+        let text = sw.grid(&sw.prop_solution).marks(&marks).to_string();
+        assert!(text.contains("\n║  1  │  2  ║ 3 4 │ 3 4 ║\n"));
+        assert!(text.contains("\n║   2 │ 1   ║ 1 2 │ 1 2 ║\n"));
+        assert_eq!(text.lines().count(), 4 * 3 + 1);
+
+        let html = sw.to_html(&[], &marks);
+        assert_eq!(html.matches("<td").count(), 16);
+        assert_eq!(html.matches("class=\"given\"").count(), 4);
+        assert!(html.contains("<div class=\"marks\"><span></span><span></span><span>3</span>"));
+        let svg = sw.to_svg(&[], &marks);
+        // Every bit but those of the four clues
+        let bits: u32 = marks.iter().map(|mask| mask.count_ones()).sum();
+        assert_eq!(svg.matches("class=\"mark\"").count(), bits as usize - 4);
+
+        sw.prop_solution.push([1, 3, 1]);
+        assert_eq!(sw.candidates(), None);
+    }
+
+    #[test]
+    fn games_take_entries_marks_undo_hints_and_checks() {
+        use game::{parse_keys, Action, Game, Key};
+
+        assert_eq!(
+            parse_keys(b"\x1b[C\x1b[Bp3\x7f\x1b\x03"),
+            vec![
+                Key::Right,
+                Key::Down,
+                Key::Char('p'),
+                Key::Char('3'),
+                Key::Backspace,
+                Key::Char('q')
+            ]
+        );
+
+        let mut sw = SudokuWeb::new(2, 2);
+        // Each row is missing one symbol: 3, 4, 4, and 2
+        sw.prop_solution = clues("12.43.1221.343.1", 4);
+        assert!(Game::new(SudokuWeb::new(2, 2)).is_err());
+        let mut game = Game::new(sw).unwrap();
+        assert_eq!(game.cursor, [1, 1]);
+
+        let keys = |game: &mut Game, keys: &[u8]| {
+            for key in parse_keys(keys) {
+                game.key(key);
+            }
+        };
+        keys(&mut game, b"ll2");
+        assert_eq!(game.entries, vec![[1, 3, 2]]);
+        assert!(game.web().conflicts(&game.board()).contains(&[1, 3]));
+        game.check();
+        assert_eq!(game.wrong(), vec![[1, 3]]);
+        keys(&mut game, b"ur");
+        assert_eq!((game.entries.len(), game.undo.len()), (1, 1));
+        keys(&mut game, b"3");
+        assert_eq!(
+            (game.entries.clone(), game.undo.len()),
+            (vec![[1, 3, 3]], 2)
+        );
+
+        // Pencil marks at r2c2 (the sixth cell), cleared again
+        keys(&mut game, b"jhp41");
+        assert_eq!(game.marks[5], 0b1001);
+        keys(&mut game, b" p");
+        assert_eq!(game.marks[5], 0);
+        keys(&mut game, b"4");
+        assert_eq!(game.value(2, 2), 4);
+
+        game.hint();
+        assert_eq!((game.hints, game.entries.len()), (1, 3));
+        assert!(game.screen([50, 200], true).contains("hints: 1"));
+        for [r, c, n] in game.solution.clone() {
+            game.cursor = [r, c];
+            if !game.is_given(r, c) {
+                game.enter(n);
+            }
+        }
+        assert!(game.solved());
+        assert!(game.screen([50, 200], true).contains("Solved in"));
+
+        // Without colors, the cursor is still shown, in reverse video, and nothing else is
+        let plain = game.screen([50, 200], false);
+        let codes: Vec<&str> = plain
+            .split("\x1b[")
+            .skip(1)
+            .map(|s| &s[..s.find('m').unwrap()])
+            .collect();
+        assert!(codes.contains(&"0;7"));
+        assert!(codes.iter().all(|code| ["0;7", "7", "0"].contains(code)));
+
+        // The clock stops once the puzzle is solved, and starts again when an undo unsolves it
+        let stopped = game.elapsed();
+        std::thread::sleep(std::time::Duration::from_millis(10));
+        assert_eq!(game.elapsed(), stopped);
+        keys(&mut game, b"u");
+        assert!(!game.solved());
+        std::thread::sleep(std::time::Duration::from_millis(10));
+        assert!(game.elapsed() > stopped);
+        assert_eq!(game.key(Key::Char('q')), Action::Quit);
+    }
+
+    #[test]
+    fn sessions_save_and_load_every_part_of_a_game() {
+        use game::Game;
+
+        let mut sw = SudokuWeb::new(2, 2);
+        sw.prop_solution = clues("12.43.1221.343.1", 4);
+        let mut game = Game::new(sw).unwrap();
+        game.difficulty = Some(grading::Difficulty::Easy);
+        game.cursor = [2, 2];
+        game.pencil = true;
+        game.enter(4);
+        game.pencil = false;
+        game.cursor = [1, 3];
+        game.enter(2);
+        game.enter(3);
+        game.undo();
+        game.cursor = [2, 2];
+        game.pencil = true;
+        game.hints = 2;
+        game.set_elapsed(std::time::Duration::from_millis(61_500));
+
+        let text = game.save();
+        assert!(text.starts_with("sudoku session 1\nboxes 2 2\ndifficulty Easy\nelapsed 61.5"));
+        let loaded = Game::load(&text).unwrap();
+        assert_eq!(loaded.save().lines().skip(4).collect::<Vec<_>>(), {
+            text.lines().skip(4).collect::<Vec<_>>()
+        });
+        assert_eq!(
+            (loaded.entries.clone(), loaded.marks[5]),
+            (vec![[1, 3, 2]], 0b1000)
+        );
+        assert_eq!((loaded.undo.len(), loaded.redo.len()), (2, 1));
+        assert_eq!(
+            (loaded.hints, loaded.cursor, loaded.pencil),
+            (2, [2, 2], true)
+        );
+        assert!(loaded.elapsed() >= std::time::Duration::from_millis(61_500));
+        assert_eq!(loaded.solution, game.solution);
+
+        let path = std::env::temp_dir().join(format!("session-{}.sudoku", std::process::id()));
+        game.save_file(&path).unwrap();
+        assert_eq!(Game::load_file(&path).unwrap().entries, game.entries);
+        std::fs::remove_file(&path).unwrap();
+
+        // Anything but the boxes can be left out, but not newer versions or unknown keys
+        let minimal = Game::load("sudoku session 1\nboxes 2 2\nclue 1 1 1\n");
+        assert_eq!(
+            minimal.unwrap_err(),
+            "the puzzle has more than one solution"
+        );
+        assert!(Game::load("sudoku session 2\nboxes 2 2\n")
+            .unwrap_err()
+            .contains("newer"));
+        assert_eq!(
+            Game::load("sudoku session 0\nboxes 2 2\n").unwrap_err(),
+            "bad session version: sudoku session 0"
+        );
+        assert!(Game::load(&text.replace("hints", "clues")).is_err());
+        assert!(Game::load(&text.replace("entry 1 3 2", "entry 1 1 2")).is_err());
+
+        // Numbers too big for the board, or for a clock, are errors too
+        let huge = text.replace("boxes 2 2", "boxes 4294967296 4294967296");
+        assert_eq!(
+            Game::load(&huge).unwrap_err(),
+            "line 2: bad boxes: boxes 4294967296 4294967296"
+        );
+        let forever = text.replace("elapsed 61.500", "elapsed 1e300");
+        assert_eq!(
+            Game::load(&forever).unwrap_err(),
+            "line 4: bad elapsed: elapsed 1e300"
+        );
+    }
+
+    // The 17 clue puzzle from `main`
+    const V17: &str =
+        "...8.1..........435............7.8........1...2..3....6......75..34........2..6..";
+
+    #[test]
+    fn hints_give_the_easiest_step_or_point_out_wrong_entries() {
+        use logic::{Hint, Logic, Technique};
+
+        let mut sw = SudokuWeb::new(2, 2);
+        sw.prop_solution = clues("12.43.1221.343.1", 4);
+        match sw.hint(&[]).unwrap() {
+            Hint::Step(step) => {
+                assert_eq!(step.technique, Technique::HiddenSingle);
+                assert_eq!(
+                    (step.cells, step.placement),
+                    (vec![[1, 3]], Some([1, 3, 3]))
+                );
+                assert_eq!(step.explanation, "In row 1, 3 can only go in r1c3");
+            }
+            hint => panic!("{:?}", hint),
+        }
+        let hint = sw.hint(&[[1, 3, 3], [2, 2, 1]]).unwrap();
+        assert_eq!(hint, Hint::Wrong(vec![[2, 2]]));
+        assert_eq!(hint.to_string(), "r2c2 is wrong");
+        let rest = [[1, 3, 3], [2, 2, 4], [3, 3, 4], [4, 3, 2]];
+        assert_eq!(sw.hint(&rest).unwrap(), Hint::Solved);
+        sw.prop_solution.clear();
+        assert!(sw.hint(&[]).is_err());
+
+        // A clue that contradicts the rest leaves nothing to hint at, even on a web that has
+        // already found the puzzle unique
+        for backend in [Backend::Dance, Backend::Propagate] {
+            let mut sw = SudokuWeb::new(2, 2);
+            sw.backend = backend;
+            sw.prop_solution = clues("12.43.1221.343.1", 4);
+            assert!(sw.hint(&[]).is_ok());
+            sw.prop_solution.push([1, 3, 1]);
+            assert!(sw.hint(&[]).is_err());
+        }
+
+        // Every step of a hard puzzle is true of its solution, and they add up to it
+        let mut sw = SudokuWeb::new(3, 3);
+        sw.prop_solution = clues(V17, 9);
+        sw.solve(2, false, false);
+        let (givens, solution) = (sw.prop_solution.clone(), sw.solution.clone());
+        let mut logic = Logic::new(&sw, &givens, &solution);
+        let mut techniques = vec![];
+        while let Some(step) = logic.next_step() {
+            assert!(step.placement.is_none_or(|id| solution.contains(&id)));
+            assert!(step.eliminations.iter().all(|id| !solution.contains(id)));
+            assert!(step.placement.is_some() || !step.eliminations.is_empty());
+            techniques.push(step.technique);
+            logic.apply(&step);
+        }
+        assert_eq!(logic.board().len(), 81);
+        assert!(logic.board().iter().all(|id| solution.contains(id)));
+        assert!(techniques.contains(&Technique::LockedCandidates));
+    }
+
+    #[test]
+    fn walkthroughs_export_every_step_as_json_and_markdown() {
+        let mut sw = SudokuWeb::new(3, 3);
+        sw.prop_solution = clues(V17, 9);
+        let walkthrough = sw.walkthrough().unwrap();
+        let stages = &walkthrough.stages;
+        assert_eq!(stages.last().unwrap().board.len(), 81);
+        assert_eq!(stages.last().unwrap().candidates, vec![0; 81]);
+        let placed = stages.iter().filter(|s| s.step.placement.is_some()).count();
+        assert_eq!(placed, 81 - 17);
+
+        let json = sw.walkthrough_json(&walkthrough);
+        assert!(json.starts_with("{\n  \"clues\": [[1,4,8],[1,6,1],"));
+        assert_eq!(json.matches("\"technique\":").count(), stages.len());
+        assert!(json.contains("\"placement\":null,\"eliminations\":[["));
+
+        let md = sw.walkthrough_markdown(&walkthrough);
+        assert!(md.contains("| Hidden single |"));
+        assert!(md.contains(&format!("\n## Step {}: ", stages.len())));
+        assert_eq!(md.matches("\n```\n╔").count(), stages.len() + 1);
+        assert!(md.contains(" can be removed from r"));
+
+        sw.prop_solution.pop();
+        sw.prop_solution.pop();
+        assert!(sw.walkthrough().is_err());
+
+        // All of it, for a puzzle small enough to check by hand
+        let mut sw = SudokuWeb::new(2, 2);
+        sw.prop_solution = clues("1234341.2143432.", 4);
+        let walkthrough = sw.walkthrough().unwrap();
+        let json = concat!(
+            "{\n",
+            "  \"clues\": [[1,1,1],[1,2,2],[1,3,3],[1,4,4],[2,1,3],[2,2,4],[2,3,1],[3,1,2],",
+            "[3,2,1],[3,3,4],[3,4,3],[4,1,4],[4,2,3],[4,3,2]],\n",
+            "  \"solution\": [[1,1,1],[1,2,2],[1,3,3],[1,4,4],[2,1,3],[2,2,4],[2,3,1],[3,1,2],",
+            "[3,2,1],[3,3,4],[3,4,3],[4,1,4],[4,2,3],[4,3,2],[2,4,2],[4,4,1]],\n",
+            "  \"steps\": [\n",
+            "    {\"technique\":\"Hidden single\",\"cells\":[[2,4]],\"placement\":[2,4,2],",
+            "\"eliminations\":[],\"explanation\":\"In row 2, 2 can only go in r2c4\",",
+            "\"board\":[[1,2,3,4],[3,4,1,2],[2,1,4,3],[4,3,2,0]],",
+            "\"candidates\":[[[],[],[],[]],[[],[],[],[]],[[],[],[],[]],[[],[],[],[1]]]},\n",
+            "    {\"technique\":\"Hidden single\",\"cells\":[[4,4]],\"placement\":[4,4,1],",
+            "\"eliminations\":[],\"explanation\":\"In row 4, 1 can only go in r4c4\",",
+            "\"board\":[[1,2,3,4],[3,4,1,2],[2,1,4,3],[4,3,2,1]],",
+            "\"candidates\":[[[],[],[],[]],[[],[],[],[]],[[],[],[],[]],[[],[],[],[]]]}\n",
+            "  ]\n",
+            "}\n"
+        );
+        assert_eq!(sw.walkthrough_json(&walkthrough), json);
+
+        // There's nothing to walk through once a clue contradicts the rest
+        sw.prop_solution.push([2, 4, 1]);
+        assert!(sw.walkthrough().is_err());
+
+        // An elimination step shows its explanation once, then the pencil marks it leaves
+        let mut sw = SudokuWeb::new(2, 3);
+        sw.prop_solution = clues(".6.1.......25......2.4.6......1.3...", 6);
+        let walkthrough = sw.walkthrough().unwrap();
+        let md = sw.walkthrough_markdown(&walkthrough);
+        let step = concat!(
+            "## Step 7: Locked candidates\n",
+            "\n",
+            "In row 1, 3 can only go in r1c5 and r1c6, which are both in box 3; ",
+            "so 3 can be removed from r2c5, r3c5, and r3c6.\n",
+            "\n",
+            "```\n",
+            "╔═══════╤═══════╦═══════╤═══════╦═══════╤═══════╗\n",
+            "║       │       ║       │       ║     3 │     3 ║\n",
+            "║   2   │   6   ║ 4 5   │   1   ║ 4 5   │ 4 5   ║\n",
+            "╟───────┼───────╫───────┼───────╫───────┼───────╢\n",
+            "║       │ 1   3 ║       │     3 ║ 1     │       ║\n",
+            "║   4   │       ║   5 6 │   5   ║   5 6 │   2   ║\n",
+            "╟───────┼───────╫───────┼───────╫───────┼───────╢\n",
+            "║       │ 1   3 ║   2   │   2 3 ║ 1     │ 1     ║\n",
+            "║   5   │       ║ 4   6 │       ║ 4   6 │ 4     ║\n",
+            "╠═══════╪═══════╬═══════╪═══════╬═══════╪═══════╣\n",
+            "║       │       ║ 1     │       ║ 1     │       ║\n",
+            "║   3   │   2   ║   5   │   4   ║   5   │   6   ║\n",
+            "╟───────┼───────╫───────┼───────╫───────┼───────╢\n",
+            "║       │       ║ 1 2   │   2   ║ 1   3 │ 1   3 ║\n",
+            "║   6   │ 4 5   ║   5   │   5   ║ 4 5   │ 4 5   ║\n",
+            "╟───────┼───────╫───────┼───────╫───────┼───────╢\n",
+            "║       │       ║       │       ║       │       ║\n",
+            "║   1   │ 4 5   ║   3   │   6   ║   2   │ 4 5   ║\n",
+            "╚═══════╧═══════╩═══════╧═══════╩═══════╧═══════╝\n",
+            "```\n",
+            "\n",
+        );
+        let start = md.find("## Step 7:").unwrap();
+        let end = md.find("## Step 8:").unwrap();
+        assert_eq!(&md[start..end], step);
+    }
+}
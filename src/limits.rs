@@ -0,0 +1,99 @@
+// Ways to stop a search before it finishes: a token another thread can cancel, a deadline, and a
+// budget of nodes (rows chosen, or guesses made, by one search)
+//
+// A web's `limits` apply to every search it does, from `solve` and so from everything built on
+// it. A search that runs out unwinds like one that has found enough solutions, leaving the web as
+// it was, and `solve` says why it stopped. Whatever it learned until then (`solution`,
+// `solution_count`) stands, but it doesn't claim to know what it didn't get to: a puzzle is never
+// `uniq` or impossible on the word of a search that stopped early.
+//
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Instant;
+
+// How often (in nodes) to look at the clock
+const CLOCK_EVERY: usize = 256;
+
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Limits {
+    pub cancel: Option<CancelToken>,
+    pub deadline: Option<Instant>,
+    pub nodes: Option<usize>,
+}
+
+// Why a search stopped
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Outcome {
+    // It looked at everything it needed to
+    #[default]
+    Finished,
+    Cancelled,
+    OutOfTime,
+    OutOfNodes,
+}
+
+impl Outcome {
+    pub fn limit_reached(self) -> bool {
+        self != Outcome::Finished
+    }
+}
+
+// What is left of the limits during one search
+#[derive(Debug)]
+pub struct Budget {
+    limits: Limits,
+    spent: usize,
+    pub outcome: Outcome,
+}
+
+impl Budget {
+    pub fn new(limits: &Limits) -> Self {
+        Budget {
+            limits: limits.clone(),
+            spent: 0,
+            outcome: Outcome::Finished,
+        }
+    }
+
+    // Spend a node. Returns false once any limit is reached (and from then on).
+    pub fn spend(&mut self) -> bool {
+        if self.outcome.limit_reached() {
+            return false;
+        }
+        self.spent += 1;
+
+        if self
+            .limits
+            .cancel
+            .as_ref()
+            .is_some_and(|c| c.is_cancelled())
+        {
+            self.outcome = Outcome::Cancelled;
+        } else if self.limits.nodes.is_some_and(|nodes| self.spent > nodes) {
+            self.outcome = Outcome::OutOfNodes;
+        } else if self.spent % CLOCK_EVERY == 1
+            && self.limits.deadline.is_some_and(|d| Instant::now() >= d)
+        {
+            self.outcome = Outcome::OutOfTime;
+        }
+        !self.outcome.limit_reached()
+    }
+}
@@ -0,0 +1,373 @@
+// Constraints along lines and paths of cells: thermometers, arrows, and sandwiches, and killer
+// cages
+//
+// They are read from a text description, one per line:
+//
+//     # Anything after a `#` is a comment
+//     thermo r1c1 r2c2 r3c2    # from the bulb, each cell is greater than the one before
+//     arrow r5c5 r4c6 r3c7     # the circle (first) is the sum of the rest of the arrow
+//     sandwich row 3 15        # between the 1 and the 9 of row 3, the digits add up to 15
+//     sandwich col 7 0         # the 1 and the 9 of column 7 touch
+//     killer 12 r8c1 r8c2 r9c1 # the cage adds up to 12, with no digit repeated
+//
+// Thermometers and arrows are paths, so each cell must touch the one before it, orthogonally or
+// diagonally. Sandwiches are between the smallest and largest symbols, whatever the size of the
+// grid.
+//
+
+use rules::{highest, lowest, span, Board, Rule};
+use std::fmt;
+
+use relations::{on_board, parse_cell};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Line {
+    Row(usize),
+    Column(usize),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Shape {
+    Thermo(Vec<[usize; 2]>),
+    Arrow(Vec<[usize; 2]>),
+    Sandwich(Line, usize),
+    Killer(Vec<[usize; 2]>, usize),
+}
+
+impl Shape {
+    // The cells this shape constrains, in order
+    fn cells(&self, board: &Board) -> Vec<[usize; 2]> {
+        match self {
+            Shape::Thermo(cells) | Shape::Arrow(cells) | Shape::Killer(cells, _) => cells.clone(),
+            Shape::Sandwich(Line::Row(r), _) => (1..=board.width()).map(|c| [*r, c]).collect(),
+            Shape::Sandwich(Line::Column(c), _) => (1..=board.height()).map(|r| [r, *c]).collect(),
+        }
+    }
+
+    // Could the empty (0) `values` of this shape's cells still be filled in to satisfy it? With
+    // every value filled in, this is whether it is satisfied.
+    fn allows(&self, values: &[usize], nums: usize) -> bool {
+        match self {
+            Shape::Thermo(_) => {
+                // Leave room for the cells before and after each value
+                let room = values
+                    .iter()
+                    .enumerate()
+                    .all(|(i, v)| *v == 0 || (*v > i && *v + (values.len() - 1 - i) <= nums));
+                room && values.iter().enumerate().all(|(i, v)| {
+                    values[i + 1..]
+                        .iter()
+                        .enumerate()
+                        .all(|(gap, w)| *v == 0 || *w == 0 || *w > *v + gap)
+                })
+            }
+            Shape::Arrow(_) => {
+                let (circle, arrow) = values.split_first().unwrap();
+                let empty = arrow.iter().filter(|v| **v == 0).count();
+                let sum: usize = arrow.iter().sum();
+                let (low, high) = (sum + empty, sum + empty * nums);
+                match circle {
+                    0 => low <= nums,
+                    _ => low <= *circle && *circle <= high,
+                }
+            }
+            Shape::Sandwich(_, total) => {
+                let ends = (
+                    values.iter().position(|v| *v == 1),
+                    values.iter().position(|v| *v == nums),
+                );
+                let (a, b) = match ends {
+                    (Some(a), Some(b)) => (a.min(b), a.max(b)),
+                    _ => return true,
+                };
+                let between = &values[a + 1..b];
+                let empty = between.iter().filter(|v| **v == 0).count();
+                let sum: usize = between.iter().sum();
+                sum + empty * 2 <= *total && *total <= sum + empty * (nums - 1)
+            }
+            Shape::Killer(_, total) => {
+                let placed: Vec<usize> = values.iter().copied().filter(|v| *v > 0).collect();
+                let repeats = placed
+                    .iter()
+                    .enumerate()
+                    .any(|(i, v)| placed[i + 1..].contains(v));
+                // The empty cells hold at least the smallest unused symbols, and at most the
+                // largest
+                let unused: Vec<usize> = (1..=nums).filter(|n| !placed.contains(n)).collect();
+                let empty = values.len() - placed.len();
+                if repeats || empty > unused.len() {
+                    return false;
+                }
+                let sum: usize = placed.iter().sum();
+                let low: usize = unused[..empty].iter().sum();
+                let high: usize = unused[unused.len() - empty..].iter().sum();
+                sum + low <= *total && *total <= sum + high
+            }
+        }
+    }
+
+    // Narrow the domains of this shape's cells (see `Rule::narrow`) by the bounds on each of
+    // them: a thermometer's cells must leave room for the cells before and after, and a cell of
+    // an arrow or a cage can't hold more than is left once the others hold as little as they can.
+    // A sandwich's ends must be far enough apart, but not too far.
+    fn narrow(&self, board: &Board, domains: &mut [u64]) {
+        let indices: Vec<usize> = self
+            .cells(board)
+            .iter()
+            .filter(|[r, c]| board.contains(*r, *c))
+            .map(|[r, c]| board.index(*r, *c))
+            .collect();
+        if indices.iter().any(|i| domains[*i] == 0) {
+            return;
+        }
+        match self {
+            Shape::Thermo(_) => {
+                let mut low = 0;
+                for i in indices.iter() {
+                    domains[*i] &= span(low + 1, 64);
+                    if domains[*i] == 0 {
+                        return;
+                    }
+                    low = lowest(domains[*i]);
+                }
+                let mut high = 65;
+                for i in indices.iter().rev() {
+                    domains[*i] &= span(1, high - 1);
+                    if domains[*i] == 0 {
+                        return;
+                    }
+                    high = highest(domains[*i]);
+                }
+            }
+            Shape::Arrow(_) | Shape::Killer(_, _) => {
+                let (mut total, parts) = match self {
+                    Shape::Killer(_, total) => (span(*total, *total), &indices[..]),
+                    _ => (domains[indices[0]], &indices[1..]),
+                };
+                let low: usize = parts.iter().map(|i| lowest(domains[*i])).sum();
+                let high: usize = parts.iter().map(|i| highest(domains[*i])).sum();
+                if let Shape::Arrow(_) = self {
+                    domains[indices[0]] &= span(low, high);
+                    total = domains[indices[0]];
+                }
+                if total == 0 {
+                    return;
+                }
+                for i in parts.iter() {
+                    let (part_low, part_high) = (lowest(domains[*i]), highest(domains[*i]));
+                    let (others_low, others_high) = (low - part_low, high - part_high);
+                    domains[*i] &= span(
+                        lowest(total).saturating_sub(others_high),
+                        highest(total).saturating_sub(others_low),
+                    );
+                }
+            }
+            Shape::Sandwich(_, total) => {
+                // The 1 and the largest symbol can only be where the other could be at a
+                // distance that leaves room for the sum between them
+                let nums = board.symbols();
+                let can = |i: usize, n: usize| domains[indices[i]] & 1 << (n - 1) != 0;
+                let fits = |gap: usize| {
+                    gap + 2 <= nums
+                        && (2..2 + gap).sum::<usize>() <= *total
+                        && *total <= (nums - gap..nums).sum::<usize>()
+                };
+                let mut keep = vec![(false, false); indices.len()];
+                for a in (0..indices.len()).filter(|a| can(*a, 1)) {
+                    for b in (0..indices.len()).filter(|b| *b != a && can(*b, nums)) {
+                        if fits(a.max(b) - a.min(b) - 1) {
+                            keep[a].0 = true;
+                            keep[b].1 = true;
+                        }
+                    }
+                }
+                for (i, (one, most)) in indices.iter().zip(keep) {
+                    if !one {
+                        domains[*i] &= !1;
+                    }
+                    if !most {
+                        domains[*i] &= !(1 << (nums - 1));
+                    }
+                }
+            }
+        }
+    }
+
+    // The values on `board` of this shape's cells, with `id` placed as well
+    fn values(&self, board: &Board, id: [usize; 3]) -> Vec<usize> {
+        let [r, c, n] = id;
+        self.cells(board)
+            .iter()
+            .map(|cell| match cell {
+                [cr, cc] if [*cr, *cc] == [r, c] => n,
+                [cr, cc] => board.get(*cr, *cc),
+            })
+            .collect()
+    }
+}
+
+impl fmt::Display for Shape {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (name, cells) = match self {
+            Shape::Thermo(cells) => ("thermo", cells),
+            Shape::Arrow(cells) => ("arrow", cells),
+            Shape::Killer(cells, total) => {
+                write!(f, "killer {}", total)?;
+                ("", cells)
+            }
+            Shape::Sandwich(Line::Row(r), total) => {
+                return write!(f, "sandwich row {} {}", r, total)
+            }
+            Shape::Sandwich(Line::Column(c), total) => {
+                return write!(f, "sandwich col {} {}", c, total)
+            }
+        };
+        write!(f, "{}", name)?;
+        for [r, c] in cells.iter() {
+            write!(f, " r{}c{}", r, c)?;
+        }
+        Ok(())
+    }
+}
+
+// Every thermometer, arrow, sandwich, and killer cage on the board
+#[derive(Debug, Clone, Default)]
+pub struct Lines {
+    pub shapes: Vec<Shape>,
+}
+
+impl Lines {
+    // Read the shapes for a board `height` cells tall and `width` wide
+    pub fn parse(text: &str, height: usize, width: usize) -> Result<Self, String> {
+        let mut lines = Lines::default();
+        for (line_i, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or("");
+            let words: Vec<&str> = line.split_whitespace().collect();
+            let bad = |why: &str| Err(format!("line {}: {}: {}", line_i + 1, why, line.trim()));
+            match words.as_slice() {
+                [] => (),
+                [kind @ "thermo", path @ ..] | [kind @ "arrow", path @ ..] => {
+                    let cells: Option<Vec<[usize; 2]>> =
+                        path.iter().map(|w| parse_cell(w)).collect();
+                    let cells = match cells {
+                        Some(cells) => cells,
+                        None => return bad("cells look like r1c2"),
+                    };
+                    if !cells.iter().all(|cell| on_board(*cell, height, width)) {
+                        return bad("the cells must be on the board");
+                    }
+                    if cells.len() < 2 {
+                        return bad("a path needs at least two cells");
+                    }
+                    let touching = cells.windows(2).all(|pair| {
+                        let [[r1, c1], [r2, c2]] = [pair[0], pair[1]];
+                        pair[0] != pair[1]
+                            && r1.max(r2) - r1.min(r2) <= 1
+                            && c1.max(c2) - c1.min(c2) <= 1
+                    });
+                    if !touching {
+                        return bad("each cell of a path must touch the one before it");
+                    }
+                    lines.shapes.push(match *kind {
+                        "thermo" => Shape::Thermo(cells),
+                        _ => Shape::Arrow(cells),
+                    });
+                }
+                ["killer", total, cage @ ..] => {
+                    let cells: Option<Vec<[usize; 2]>> =
+                        cage.iter().map(|w| parse_cell(w)).collect();
+                    let (total, cells) = match (total.parse(), cells) {
+                        (Ok(total), Some(cells)) => (total, cells),
+                        _ => return bad("expected a sum, then cells like r1c2"),
+                    };
+                    if !cells.iter().all(|cell| on_board(*cell, height, width)) {
+                        return bad("the cells must be on the board");
+                    }
+                    let repeats = cells
+                        .iter()
+                        .enumerate()
+                        .any(|(i, cell)| cells[i + 1..].contains(cell));
+                    if cells.is_empty() || repeats {
+                        return bad("a cage needs some cells, each only once");
+                    }
+                    lines.shapes.push(Shape::Killer(cells, total));
+                }
+                ["sandwich", line, index, total] => {
+                    let (index, total) = match (index.parse(), total.parse()) {
+                        (Ok(index), Ok(total)) if index > 0 => (index, total),
+                        _ => return bad("expected a row or column number and a sum"),
+                    };
+                    let line = match *line {
+                        "row" if index <= height => Line::Row(index),
+                        "col" if index <= width => Line::Column(index),
+                        "row" | "col" => return bad("the line must be on the board"),
+                        _ => return bad("a sandwich is in a `row` or a `col`"),
+                    };
+                    lines.shapes.push(Shape::Sandwich(line, total));
+                }
+                _ => return bad("expected `thermo`, `arrow`, `sandwich`, or `killer`"),
+            }
+        }
+        Ok(lines)
+    }
+
+    // Does the full grid on `board` satisfy every shape? If not, says which one it breaks.
+    pub fn verify(&self, board: &Board) -> Result<(), String> {
+        for shape in self.shapes.iter() {
+            let values: Vec<usize> = shape
+                .cells(board)
+                .iter()
+                .map(|[r, c]| board.get(*r, *c))
+                .collect();
+            if values.contains(&0) {
+                return Err(format!("not filled in: {}", shape));
+            }
+            if !shape.allows(&values, board.symbols()) {
+                return Err(format!("broken: {}", shape));
+            }
+        }
+        Ok(())
+    }
+
+    fn shapes_at<'a>(
+        &'a self,
+        board: &'a Board,
+        r: usize,
+        c: usize,
+    ) -> impl Iterator<Item = &'a Shape> {
+        self.shapes
+            .iter()
+            .filter(move |shape| shape.cells(board).contains(&[r, c]))
+    }
+}
+
+impl Rule for Lines {
+    fn admits(&self, board: &Board, id: [usize; 3]) -> bool {
+        let [r, c, _] = id;
+        self.shapes_at(board, r, c)
+            .all(|shape| shape.allows(&shape.values(board, id), board.symbols()))
+    }
+
+    fn watches(&self, board: &Board, r: usize, c: usize) -> Vec<[usize; 2]> {
+        self.shapes_at(board, r, c)
+            .flat_map(|shape| shape.cells(board))
+            .filter(|cell| *cell != [r, c])
+            .collect()
+    }
+
+    fn narrow(&self, board: &Board, domains: &mut [u64]) {
+        for shape in self.shapes.iter() {
+            shape.narrow(board, domains);
+        }
+    }
+
+    fn cages(&self) -> Vec<(Vec<[usize; 2]>, usize)> {
+        self.shapes
+            .iter()
+            .filter_map(|shape| match shape {
+                Shape::Killer(cells, total) => Some((cells.clone(), *total)),
+                _ => None,
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,734 @@
+// Solving the way people do: one deduction at a time, each with a name and a reason
+//
+// `Logic` keeps what a person would have on paper: the symbols placed so far, and the candidates
+// (pencil marks) left in every other cell. A candidate is dropped as soon as a placed symbol
+// shares a column of the cover with it, or the rules (see `Rule::narrow` and `Rule::admits`) rule
+// it out; those are bookkeeping, not steps. The steps are found on the columns, so they work on
+// any arrangement of grids:
+//
+// - a hidden single is a row, column, or box with one place left for a symbol
+// - a naked single is a cell with one candidate left
+// - locked candidates are the places left for a symbol in one unit all lying in another, so the
+//   symbol can't go anywhere else in that other unit (pointing and claiming)
+// - a naked subset is `k` cells of a unit with only `k` candidates between them, which no other
+//   cell of the unit can then have
+// - a hidden subset is `k` symbols with only `k` places left in a unit, which can then hold
+//   nothing else
+// - an X-Wing is a symbol with two places left in each of two rows, in the same two columns (or
+//   the other way around), which the symbol must then fill
+//
+// When none of them applies, the next step is taken from the solution, as backtracking.
+//
+
+use rules::{span, Board};
+use std::collections::HashMap;
+use std::fmt;
+use {Knowing, SudokuWeb};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Technique {
+    HiddenSingle,
+    NakedSingle,
+    LockedCandidates,
+    // Of 2, 3, or 4 cells or symbols
+    NakedSubset(usize),
+    HiddenSubset(usize),
+    XWing,
+    Backtracking,
+}
+
+impl Technique {
+    pub fn name(self) -> &'static str {
+        match self {
+            Technique::HiddenSingle => "Hidden single",
+            Technique::NakedSingle => "Naked single",
+            Technique::LockedCandidates => "Locked candidates",
+            Technique::NakedSubset(2) => "Naked pair",
+            Technique::NakedSubset(3) => "Naked triple",
+            Technique::NakedSubset(_) => "Naked quad",
+            Technique::HiddenSubset(2) => "Hidden pair",
+            Technique::HiddenSubset(3) => "Hidden triple",
+            Technique::HiddenSubset(_) => "Hidden quad",
+            Technique::XWing => "X-Wing",
+            Technique::Backtracking => "Backtracking",
+        }
+    }
+}
+
+// One deduction: a symbol placed, or candidates eliminated
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Step {
+    pub technique: Technique,
+    // The cells the deduction is drawn from
+    pub cells: Vec<[usize; 2]>,
+    pub placement: Option<[usize; 3]>,
+    pub eliminations: Vec<[usize; 3]>,
+    pub explanation: String,
+}
+
+impl fmt::Display for Step {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.technique.name(), self.explanation)
+    }
+}
+
+// What to tell a player who asks for help
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Hint {
+    // Entries that don't match the solution
+    Wrong(Vec<[usize; 2]>),
+    Step(Step),
+    Solved,
+}
+
+impl fmt::Display for Hint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Hint::Wrong(cells) => {
+                let names: Vec<String> = cells.iter().map(|cell| cell_name(*cell)).collect();
+                let verb = if cells.len() == 1 { "is" } else { "are" };
+                write!(f, "{} {} wrong", list(&names), verb)
+            }
+            Hint::Step(step) => step.fmt(f),
+            Hint::Solved => write!(f, "The puzzle is solved"),
+        }
+    }
+}
+
+pub fn cell_name([r, c]: [usize; 2]) -> String {
+    format!("r{}c{}", r, c)
+}
+
+// "a", "a and b", or "a, b, and c"
+fn list(items: &[String]) -> String {
+    match items {
+        [] => String::new(),
+        [one] => one.clone(),
+        [one, two] => format!("{} and {}", one, two),
+        [rest @ .., last] => format!("{}, and {}", rest.join(", "), last),
+    }
+}
+
+fn cell_list(cells: &[[usize; 2]]) -> String {
+    let names: Vec<String> = cells.iter().map(|cell| cell_name(*cell)).collect();
+    list(&names)
+}
+
+// "7 can be removed from r1c5 and r1c9", and then "8 from r1c7" for each other symbol
+fn removals(eliminations: &[[usize; 3]]) -> String {
+    let mut symbols: Vec<usize> = eliminations.iter().map(|[_, _, n]| *n).collect();
+    symbols.sort_unstable();
+    symbols.dedup();
+    let clauses: Vec<String> = symbols
+        .iter()
+        .enumerate()
+        .map(|(i, n)| {
+            let cells: Vec<[usize; 2]> = eliminations
+                .iter()
+                .filter(|[_, _, m]| m == n)
+                .map(|[r, c, _]| [*r, *c])
+                .collect();
+            let verb = if i == 0 { " can be removed" } else { "" };
+            format!("{}{} from {}", n, verb, cell_list(&cells))
+        })
+        .collect();
+    list(&clauses)
+}
+
+// Every way of picking `k` of `0..n`, in order
+fn combinations(n: usize, k: usize) -> Vec<Vec<usize>> {
+    let mut all = vec![];
+    let mut pick = vec![];
+    fn extend(start: usize, n: usize, k: usize, pick: &mut Vec<usize>, all: &mut Vec<Vec<usize>>) {
+        if pick.len() == k {
+            all.push(pick.clone());
+            return;
+        }
+        for i in start..n {
+            pick.push(i);
+            extend(i + 1, n, k, pick, all);
+            pick.pop();
+        }
+    }
+    extend(0, n, k, &mut pick, &mut all);
+    all
+}
+
+// A row, column, or box: the primary columns for each of its symbols
+struct Unit {
+    name: String,
+    // The column for each symbol, by symbol
+    columns: HashMap<usize, usize>,
+    cells: Vec<[usize; 2]>,
+}
+
+pub struct Logic<'a> {
+    web: &'a SudokuWeb,
+    solution: &'a [[usize; 3]],
+    // The `[r, c, n]` rows of each column, as `rows_of_columns` gives them
+    rows: Vec<Vec<[usize; 3]>>,
+    units: Vec<Unit>,
+    // Laid out like the board (see `Board::index`): the symbol placed in each cell (or 0), and
+    // the candidates left in each empty cell
+    values: Vec<usize>,
+    masks: Vec<u64>,
+}
+
+impl<'a> Logic<'a> {
+    // The puzzle with `placed` filled in, and every candidate left that doesn't clash with it.
+    // `solution` is only for backtracking.
+    pub fn new(web: &'a SudokuWeb, placed: &[[usize; 3]], solution: &'a [[usize; 3]]) -> Self {
+        let rows = web.rows_of_columns();
+
+        let mut units: Vec<Unit> = vec![];
+        let mut unit_of: HashMap<[usize; 3], usize> = HashMap::new();
+        for (i, key) in web.column_keys.iter().enumerate() {
+            if key[0] == 0 {
+                continue;
+            }
+            let unit = *unit_of.entry([key[0], key[1], key[2]]).or_insert_with(|| {
+                units.push(Unit {
+                    name: web.unit_name(i),
+                    columns: HashMap::new(),
+                    cells: vec![],
+                });
+                units.len() - 1
+            });
+            units[unit].columns.insert(key[3], i);
+            for [r, c, _] in rows[i].iter() {
+                if !units[unit].cells.contains(&[*r, *c]) {
+                    units[unit].cells.push([*r, *c]);
+                }
+            }
+        }
+
+        let size = web.height() * web.width();
+        let candidates = web.prop_candidates.iter().chain(std::iter::repeat(&!0));
+        let mut masks = vec![0; size];
+        for ([r, c], mask) in web.cells().into_iter().zip(candidates) {
+            masks[(r - 1) * web.width() + (c - 1)] =
+                web.allowed_at(r, c) & mask & span(1, web.symbols());
+        }
+        let mut logic = Logic {
+            web,
+            solution,
+            rows,
+            units,
+            values: vec![0; size],
+            masks,
+        };
+        for id in placed.iter() {
+            logic.place(*id);
+        }
+        logic.prune();
+        logic
+    }
+
+    fn index(&self, r: usize, c: usize) -> usize {
+        (r - 1) * self.web.width() + (c - 1)
+    }
+
+    pub fn value(&self, r: usize, c: usize) -> usize {
+        self.values[self.index(r, c)]
+    }
+
+    pub fn has(&self, [r, c, n]: [usize; 3]) -> bool {
+        self.masks[self.index(r, c)] & 1 << (n - 1) != 0
+    }
+
+    // The symbols placed so far
+    pub fn board(&self) -> Vec<[usize; 3]> {
+        self.web
+            .cells()
+            .into_iter()
+            .map(|[r, c]| [r, c, self.value(r, c)])
+            .filter(|[_, _, n]| *n > 0)
+            .collect()
+    }
+
+    // The candidates left in each of `cells()`, as bit masks like `prop_candidates` (with
+    // nothing in the cells filled in)
+    pub fn marks(&self) -> Vec<u64> {
+        self.web
+            .cells()
+            .into_iter()
+            .map(|[r, c]| self.masks[self.index(r, c)])
+            .collect()
+    }
+
+    pub fn solved(&self) -> bool {
+        self.web.cells().iter().all(|[r, c]| self.value(*r, *c) > 0)
+    }
+
+    // Place `[r, c, n]`, and drop every candidate that shares a column with it
+    fn place(&mut self, [r, c, n]: [usize; 3]) {
+        let i = self.index(r, c);
+        self.values[i] = n;
+        self.masks[i] = 0;
+        for column in self.web.indices_from_rcn(r, c, n) {
+            for [r2, c2, n2] in self.rows[column].clone() {
+                let j = self.index(r2, c2);
+                self.masks[j] &= !(1 << (n2 - 1));
+            }
+        }
+    }
+
+    // Drop the candidates the rules rule out, until they rule out no more
+    fn prune(&mut self) {
+        if self.web.rules.is_empty() {
+            return;
+        }
+        let web = self.web;
+        loop {
+            let board = rules_board(web, &self.values);
+            let mut domains: Vec<u64> = (0..self.values.len())
+                .map(|i| match self.values[i] {
+                    0 => self.masks[i],
+                    n => 1 << (n - 1),
+                })
+                .collect();
+            for rule in web.rules.iter() {
+                rule.narrow(&board, &mut domains);
+            }
+            let mut changed = false;
+            for [r, c] in web.cells() {
+                let i = self.index(r, c);
+                if self.values[i] > 0 {
+                    continue;
+                }
+                let mut mask = self.masks[i] & domains[i];
+                for n in 1..=web.symbols() {
+                    if mask & 1 << (n - 1) != 0
+                        && !web.rules.iter().all(|rule| rule.admits(&board, [r, c, n]))
+                    {
+                        mask &= !(1 << (n - 1));
+                    }
+                }
+                if mask != self.masks[i] {
+                    self.masks[i] = mask;
+                    changed = true;
+                }
+            }
+            if !changed {
+                return;
+            }
+        }
+    }
+
+    pub fn apply(&mut self, step: &Step) {
+        if let Some(id) = step.placement {
+            self.place(id);
+        }
+        for [r, c, n] in step.eliminations.iter() {
+            let i = self.index(*r, *c);
+            self.masks[i] &= !(1 << (n - 1));
+        }
+        self.prune();
+    }
+
+    // The rows of `column` still open: not yet covered by anything placed, and still candidates
+    fn live(&self, column: usize) -> Vec<[usize; 3]> {
+        let covered = self.rows[column]
+            .iter()
+            .any(|[r, c, n]| self.value(*r, *c) == *n);
+        if covered {
+            return vec![];
+        }
+        self.rows[column]
+            .iter()
+            .filter(|id| self.has(**id))
+            .copied()
+            .collect()
+    }
+
+    // The easiest step there is, or `None` once solved (or if there is nothing to backtrack to)
+    pub fn next_step(&self) -> Option<Step> {
+        if self.solved() {
+            return None;
+        }
+        self.hidden_single()
+            .or_else(|| self.naked_single())
+            .or_else(|| self.locked_candidates())
+            .or_else(|| self.naked_subset(2))
+            .or_else(|| self.hidden_subset(2))
+            .or_else(|| self.naked_subset(3))
+            .or_else(|| self.hidden_subset(3))
+            .or_else(|| self.x_wing())
+            .or_else(|| self.naked_subset(4))
+            .or_else(|| self.hidden_subset(4))
+            .or_else(|| self.backtracking())
+    }
+
+    fn hidden_single(&self) -> Option<Step> {
+        for (i, key) in self.web.column_keys.iter().enumerate() {
+            if key[0] == 0 {
+                continue;
+            }
+            if let [[r, c, n]] = self.live(i)[..] {
+                return Some(Step {
+                    technique: Technique::HiddenSingle,
+                    cells: vec![[r, c]],
+                    placement: Some([r, c, n]),
+                    eliminations: vec![],
+                    explanation: format!(
+                        "In {}, {} can only go in r{}c{}",
+                        self.web.unit_name(i),
+                        n,
+                        r,
+                        c
+                    ),
+                });
+            }
+        }
+        None
+    }
+
+    fn naked_single(&self) -> Option<Step> {
+        for [r, c] in self.web.cells() {
+            let mask = self.masks[self.index(r, c)];
+            if self.value(r, c) == 0 && mask.count_ones() == 1 {
+                let n = mask.trailing_zeros() as usize + 1;
+                return Some(Step {
+                    technique: Technique::NakedSingle,
+                    cells: vec![[r, c]],
+                    placement: Some([r, c, n]),
+                    eliminations: vec![],
+                    explanation: format!("r{}c{} has one candidate left, {}", r, c, n),
+                });
+            }
+        }
+        None
+    }
+
+    fn locked_candidates(&self) -> Option<Step> {
+        for (a, key) in self.web.column_keys.iter().enumerate() {
+            if key[0] == 0 {
+                continue;
+            }
+            let live = self.live(a);
+            if live.len() < 2 {
+                continue;
+            }
+            // The other units every place left is in
+            let mut shared = self
+                .web
+                .indices_from_rcn(live[0][0], live[0][1], live[0][2]);
+            shared.retain(|b| {
+                *b != a
+                    && *b < self.web.constraints()
+                    && self.web.column_keys[*b][0] != 0
+                    && live
+                        .iter()
+                        .all(|[r, c, n]| self.web.indices_from_rcn(*r, *c, *n).contains(b))
+            });
+            for b in shared {
+                let eliminations: Vec<[usize; 3]> = self
+                    .live(b)
+                    .into_iter()
+                    .filter(|id| !live.contains(id))
+                    .collect();
+                if eliminations.is_empty() {
+                    continue;
+                }
+                let cells: Vec<[usize; 2]> = live.iter().map(|[r, c, _]| [*r, *c]).collect();
+                let explanation = format!(
+                    "In {}, {} can only go in {}, which are {} in {}; so {}",
+                    self.web.unit_name(a),
+                    key[3],
+                    cell_list(&cells),
+                    if cells.len() == 2 { "both" } else { "all" },
+                    self.web.unit_name(b),
+                    removals(&eliminations)
+                );
+                return Some(Step {
+                    technique: Technique::LockedCandidates,
+                    cells,
+                    placement: None,
+                    eliminations,
+                    explanation,
+                });
+            }
+        }
+        None
+    }
+
+    fn naked_subset(&self, k: usize) -> Option<Step> {
+        for unit in self.units.iter() {
+            let empty: Vec<[usize; 2]> = unit
+                .cells
+                .iter()
+                .filter(|[r, c]| self.value(*r, *c) == 0)
+                .copied()
+                .collect();
+            if empty.len() <= k {
+                continue;
+            }
+            let open: Vec<[usize; 2]> = empty
+                .iter()
+                .filter(|[r, c]| {
+                    let count = self.masks[self.index(*r, *c)].count_ones() as usize;
+                    count >= 2 && count <= k
+                })
+                .copied()
+                .collect();
+            for pick in combinations(open.len(), k) {
+                let cells: Vec<[usize; 2]> = pick.iter().map(|i| open[*i]).collect();
+                let union = cells
+                    .iter()
+                    .fold(0, |mask, [r, c]| mask | self.masks[self.index(*r, *c)]);
+                if union.count_ones() as usize != k {
+                    continue;
+                }
+                let symbols: Vec<usize> = (1..=self.web.symbols())
+                    .filter(|n| union & 1 << (n - 1) != 0)
+                    .collect();
+                let mut eliminations = vec![];
+                for [r, c] in empty.iter().filter(|cell| !cells.contains(cell)) {
+                    for n in symbols.iter() {
+                        if self.has([*r, *c, *n]) && unit.columns.contains_key(n) {
+                            eliminations.push([*r, *c, *n]);
+                        }
+                    }
+                }
+                if eliminations.is_empty() {
+                    continue;
+                }
+                let technique = Technique::NakedSubset(k);
+                let names: Vec<String> = symbols.iter().map(|n| n.to_string()).collect();
+                let explanation = format!(
+                    "{} can only hold {} between them ({} in {}); so {}",
+                    cell_list(&cells),
+                    list(&names),
+                    technique.name().to_lowercase(),
+                    unit.name,
+                    removals(&eliminations)
+                );
+                return Some(Step {
+                    technique,
+                    cells,
+                    placement: None,
+                    eliminations,
+                    explanation,
+                });
+            }
+        }
+        None
+    }
+
+    fn hidden_subset(&self, k: usize) -> Option<Step> {
+        for unit in self.units.iter() {
+            // The symbols left to place in the unit, with their places
+            let mut open: Vec<(usize, Vec<[usize; 2]>)> = vec![];
+            for n in 1..=self.web.symbols() {
+                if let Some(column) = unit.columns.get(&n) {
+                    let live = self.live(*column);
+                    if live.len() >= 2 && live.len() <= k {
+                        open.push((n, live.iter().map(|[r, c, _]| [*r, *c]).collect()));
+                    }
+                }
+            }
+            for pick in combinations(open.len(), k) {
+                let mut cells: Vec<[usize; 2]> = vec![];
+                for i in pick.iter() {
+                    for cell in open[*i].1.iter() {
+                        if !cells.contains(cell) {
+                            cells.push(*cell);
+                        }
+                    }
+                }
+                if cells.len() != k {
+                    continue;
+                }
+                cells.sort_unstable();
+                let symbols: Vec<usize> = pick.iter().map(|i| open[*i].0).collect();
+                let mut eliminations = vec![];
+                for [r, c] in cells.iter() {
+                    for n in (1..=self.web.symbols()).filter(|n| !symbols.contains(n)) {
+                        if self.has([*r, *c, n]) {
+                            eliminations.push([*r, *c, n]);
+                        }
+                    }
+                }
+                if eliminations.is_empty() {
+                    continue;
+                }
+                let technique = Technique::HiddenSubset(k);
+                let names: Vec<String> = symbols.iter().map(|n| n.to_string()).collect();
+                let explanation = format!(
+                    "In {}, {} can only go in {} ({}); so {}",
+                    unit.name,
+                    list(&names),
+                    cell_list(&cells),
+                    technique.name().to_lowercase(),
+                    removals(&eliminations)
+                );
+                return Some(Step {
+                    technique,
+                    cells,
+                    placement: None,
+                    eliminations,
+                    explanation,
+                });
+            }
+        }
+        None
+    }
+
+    fn x_wing(&self) -> Option<Step> {
+        let keys = &self.web.column_keys;
+        // Rows (1) against columns (2), then the other way around, of the same grid
+        for kind in [1, 2] {
+            let pairs: Vec<(usize, Vec<[usize; 3]>)> = (0..keys.len())
+                .filter(|i| keys[*i][0] == kind)
+                .map(|i| (i, self.live(i)))
+                .filter(|(_, live)| live.len() == 2)
+                .collect();
+            for (x, (a1, live1)) in pairs.iter().enumerate() {
+                for (a2, live2) in pairs[x + 1..].iter() {
+                    let [_, g, _, n] = keys[*a1];
+                    if keys[*a2][1] != g || keys[*a2][3] != n {
+                        continue;
+                    }
+                    // The unit of the other kind, in the same grid, that each place is in
+                    let across = |[r, c, n]: [usize; 3]| {
+                        self.web
+                            .indices_from_rcn(r, c, n)
+                            .into_iter()
+                            .find(|i| keys[*i][0] == 3 - kind && keys[*i][1] == g)
+                    };
+                    let b = [across(live1[0]), across(live1[1])];
+                    let d = [across(live2[0]), across(live2[1])];
+                    let (b1, b2) = match b {
+                        [Some(b1), Some(b2)] if b1 != b2 => (b1, b2),
+                        _ => continue,
+                    };
+                    if !(d == [Some(b1), Some(b2)] || d == [Some(b2), Some(b1)]) {
+                        continue;
+                    }
+                    let corners: Vec<[usize; 3]> = live1.iter().chain(live2).copied().collect();
+                    let mut eliminations: Vec<[usize; 3]> = vec![];
+                    for id in self.live(b1).into_iter().chain(self.live(b2)) {
+                        if !corners.contains(&id) {
+                            eliminations.push(id);
+                        }
+                    }
+                    if eliminations.is_empty() {
+                        continue;
+                    }
+                    let mut cells: Vec<[usize; 2]> =
+                        corners.iter().map(|[r, c, _]| [*r, *c]).collect();
+                    cells.sort_unstable();
+                    let explanation = format!(
+                        concat!(
+                            "In {} and {}, {} can only go in {}, in {} and {}: whichever way ",
+                            "round they go, they fill both; so {}"
+                        ),
+                        self.web.unit_name(*a1),
+                        self.web.unit_name(*a2),
+                        n,
+                        cell_list(&cells),
+                        self.web.unit_name(b1),
+                        self.web.unit_name(b2),
+                        removals(&eliminations)
+                    );
+                    return Some(Step {
+                        technique: Technique::XWing,
+                        cells,
+                        placement: None,
+                        eliminations,
+                        explanation,
+                    });
+                }
+            }
+        }
+        None
+    }
+
+    // Take the empty cell with the fewest candidates from the solution, when nothing else works.
+    // This is a guess checked against the solution, not a proof, and the explanation says so.
+    fn backtracking(&self) -> Option<Step> {
+        let [r, c] = self
+            .web
+            .cells()
+            .into_iter()
+            .filter(|[r, c]| self.value(*r, *c) == 0)
+            .min_by_key(|[r, c]| self.masks[self.index(*r, *c)].count_ones())?;
+        let [_, _, n] = *self.solution.iter().find(|id| [id[0], id[1]] == [r, c])?;
+        let count = self.masks[self.index(r, c)].count_ones();
+        Some(Step {
+            technique: Technique::Backtracking,
+            cells: vec![[r, c]],
+            placement: Some([r, c, n]),
+            eliminations: vec![],
+            explanation: format!(
+                concat!(
+                    "None of the other techniques applies, so r{}c{} is filled in from the ",
+                    "solution: it's {}, one of the {} candidates left there"
+                ),
+                r, c, n, count
+            ),
+        })
+    }
+}
+
+// The board the rules are asked about: every symbol placed so far
+fn rules_board(web: &SudokuWeb, values: &[usize]) -> Board {
+    let mut board = Board::new(web.height(), web.width(), web.symbols());
+    for [r, c] in web.cells() {
+        let n = values[(r - 1) * web.width() + (c - 1)];
+        if n > 0 {
+            board.set(r, c, n);
+        }
+    }
+    board
+}
+
+impl SudokuWeb {
+    // What a primary column's unit is called: `row 3`, `column 5`, `box 2` (or, with several
+    // grids, `row 3 of grid 2` and `the box at r4c7`)
+    pub fn unit_name(&self, column: usize) -> String {
+        let single = self.grids.len() == 1;
+        match self.column_keys[column] {
+            [0, r, c, _] => format!("r{}c{}", r, c),
+            [1, _, r, _] if single => format!("row {}", r),
+            [2, _, c, _] if single => format!("column {}", c),
+            [1, g, r, _] => format!("row {} of grid {}", r, g + 1),
+            [2, g, c, _] => format!("column {} of grid {}", c, g + 1),
+            [_, top, left, _] if single => {
+                let across = self.symbols() / self.belts;
+                let b = (top - 1) / self.curtains * across + (left - 1) / self.belts + 1;
+                format!("box {}", b)
+            }
+            [_, top, left, _] => format!("the box at r{}c{}", top, left),
+        }
+    }
+
+    // The next step for a player who has filled in `entries` on the puzzle in `prop_solution`,
+    // given its unique `solution`: their wrong entries, if they have any, or else the easiest
+    // deduction left
+    pub fn hint_with(&self, entries: &[[usize; 3]], solution: &[[usize; 3]]) -> Hint {
+        let wrong: Vec<[usize; 2]> = entries
+            .iter()
+            .filter(|id| !solution.contains(id))
+            .map(|[r, c, _]| [*r, *c])
+            .collect();
+        if !wrong.is_empty() {
+            return Hint::Wrong(wrong);
+        }
+        let mut placed = self.prop_solution.clone();
+        placed.extend(entries);
+        match Logic::new(self, &placed, solution).next_step() {
+            Some(step) => Hint::Step(step),
+            None => Hint::Solved,
+        }
+    }
+
+    // `hint_with`, solving the puzzle first. Fails unless the puzzle has a unique solution.
+    pub fn hint(&mut self, entries: &[[usize; 3]]) -> Result<Hint, String> {
+        if self.solve(2, false, false).limit_reached() || self.uniq != Knowing::Yes {
+            return Err("the puzzle doesn't have a unique solution".to_string());
+        }
+        let solution = self.solution.clone();
+        Ok(self.hint_with(entries, &solution))
+    }
+}
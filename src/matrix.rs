@@ -0,0 +1,197 @@
+// The exact cover matrix of a web, written out for debugging
+//
+// What's written is the matrix `solve` would start dancing on: the clues (`prop_solution`) are
+// chosen, the candidates missing from `prop_candidates` are removed, and so are the rows the
+// rules don't admit. Only the columns left uncovered and the rows left live are shown.
+//
+// Columns are named for what they stand for:
+//
+// - `r3c5`: the cell at row 3, column 5 is filled
+// - `r3#7`, `c5#7`: row 3 or column 5 has a 7 (`g1r3#7` and `g1c5#7` in grid 1, if there are
+//   several grids)
+// - `b2#7`: box 2 has a 7 (`b4,7#7` for the box whose top left cell is at row 4, column 7, if
+//   there are several grids)
+// - `s12`: secondary column 12, counting from 0
+//
+// Rows are named `r3c5=7`.
+//
+// The rows left are also the candidates still open in each cell, so `candidates` reads pencil
+// marks off of the same matrix.
+//
+
+use bitmap::{Bitmap, BLACK, WHITE};
+use {Knowing, SudokuWeb};
+
+// The pixels on each side of one entry of the matrix, which just fits a letter turned sideways
+const ENTRY: usize = 8;
+
+struct Matrix {
+    // The index, name, and size of each column
+    columns: Vec<(usize, String, usize)>,
+    // The label of each row, and the positions in `columns` of its 1s
+    rows: Vec<([usize; 3], Vec<usize>)>,
+}
+
+impl SudokuWeb {
+    pub fn column_name(&self, column: usize) -> String {
+        if column >= self.constraints() {
+            return format!("s{}", column - self.constraints());
+        }
+        let single = self.grids.len() == 1;
+        match self.column_keys[column] {
+            [0, r, c, _] => format!("r{}c{}", r, c),
+            [1, _, r, n] if single => format!("r{}#{}", r, n),
+            [2, _, c, n] if single => format!("c{}#{}", c, n),
+            [1, g, r, n] => format!("g{}r{}#{}", g, r, n),
+            [2, g, c, n] => format!("g{}c{}#{}", g, c, n),
+            [_, top, left, n] if single => {
+                let across = self.symbols() / self.belts;
+                let b = (top - 1) / self.curtains * across + (left - 1) / self.belts + 1;
+                format!("b{}#{}", b, n)
+            }
+            [_, top, left, n] => format!("b{},{}#{}", top, left, n),
+        }
+    }
+
+    // Apply the clues, candidates, and rules as `solve` does, read off what is left of the
+    // matrix, then undo it all. `None` if the clues contradict each other.
+    fn reduced_matrix(&mut self) -> Option<Matrix> {
+        let pc = self.prop_candidates.clone();
+        let eliminated = self.eliminate(&pc);
+        let ps = self.prop_solution.clone();
+        let possible = self.pre_dance(&ps);
+
+        let mut matrix = None;
+        if possible != Knowing::No {
+            let pruned = self.prune_cells(&self.cover, self.cells());
+            for row in pruned.iter() {
+                self.cover.remove_row(*row);
+            }
+
+            let columns: Vec<(usize, String, usize)> = (0..self.columns())
+                .filter(|column| !self.cover.is_covered(*column))
+                .map(|column| {
+                    let size = self.cover.rows_in(column).len();
+                    (column, self.column_name(column), size)
+                })
+                .collect();
+            let mut rows = vec![];
+            for row in (0..self.cover.rows()).filter(|row| self.cover.is_live(*row)) {
+                let [r, c, n] = *self.cover.label(row);
+                let ones = self
+                    .indices_from_rcn(r, c, n)
+                    .iter()
+                    .filter_map(|i| columns.iter().position(|(j, _, _)| j == i))
+                    .collect();
+                rows.push(([r, c, n], ones));
+            }
+            matrix = Some(Matrix { columns, rows });
+
+            for row in pruned.iter().rev() {
+                self.cover.replace_row(*row);
+            }
+            self.post_dance(&ps);
+        }
+        self.restore(&eliminated);
+        matrix
+    }
+
+    // The candidates left in each of `cells()`, as bit masks like `prop_candidates`: the symbol
+    // of each clue, and the symbols of the rows still live in the reduced matrix. `None` if the
+    // clues contradict each other.
+    pub fn candidates(&mut self) -> Option<Vec<u64>> {
+        let matrix = self.reduced_matrix()?;
+        let cells = self.cells();
+        let mut marks = vec![0; cells.len()];
+        let live = matrix.rows.iter().map(|(id, _)| id);
+        for [r, c, n] in self.prop_solution.iter().chain(live) {
+            if let Some(i) = cells.iter().position(|cell| *cell == [*r, *c]) {
+                marks[i] |= 1 << (n - 1);
+            }
+        }
+        Some(marks)
+    }
+
+    // The reduced matrix as text: a `column <index> <name> <size>` line for each column left,
+    // then a `row r3c5=7: <column indices>` line for each row left.
+    pub fn matrix_text(&mut self) -> String {
+        let matrix = match self.reduced_matrix() {
+            Some(matrix) => matrix,
+            None => return "# the clues contradict each other\n".to_string(),
+        };
+
+        let mut text = format!(
+            "# {} of {} columns ({} secondary) and {} of {} rows left after {} clues\n",
+            matrix.columns.len(),
+            self.columns(),
+            self.secondary.len(),
+            matrix.rows.len(),
+            self.cover.rows(),
+            self.prop_solution.len()
+        );
+        for (column, name, size) in matrix.columns.iter() {
+            text += &format!("column {} {} {}\n", column, name, size);
+        }
+        for ([r, c, n], ones) in matrix.rows.iter() {
+            text += &format!("row r{}c{}={}:", r, c, n);
+            for i in ones {
+                text += &format!(" {}", matrix.columns[*i].0);
+            }
+            text += "\n";
+        }
+        text
+    }
+
+    // The reduced matrix as a picture, with each column's name written up above it and each
+    // row's name to its left. A 1 is a filled square in a primary column, and a hollow one in a
+    // secondary column. Write it out with `to_pbm` or `to_png`.
+    pub fn matrix_bitmap(&mut self) -> Bitmap {
+        let matrix = self.reduced_matrix().unwrap_or(Matrix {
+            columns: vec![],
+            rows: vec![],
+        });
+
+        let column_names: Vec<String> = matrix.columns.iter().map(|c| c.1.clone()).collect();
+        let row_names: Vec<String> = matrix
+            .rows
+            .iter()
+            .map(|([r, c, n], _)| format!("r{}c{}={}", r, c, n))
+            .collect();
+        let widest = |names: &[String]| {
+            let width = names.iter().map(|name| Bitmap::text_width(name, 1)).max();
+            width.unwrap_or(0) + 4
+        };
+        let (top, left) = (widest(&column_names), widest(&row_names));
+
+        let mut bitmap = Bitmap::new(
+            left + matrix.columns.len() * ENTRY,
+            top + matrix.rows.len() * ENTRY,
+            WHITE,
+        );
+        for (i, (column, _, _)) in matrix.columns.iter().enumerate() {
+            let x = left + i * ENTRY;
+            bitmap.draw_text_up(x, top - 2, &column_names[i], 1, BLACK);
+            // A line between each kind of column
+            let kind = |column: usize| match self.column_keys.get(column) {
+                Some(key) => key[0],
+                None => 4,
+            };
+            if i > 0 && kind(*column) != kind(matrix.columns[i - 1].0) {
+                bitmap.fill_rect(x, top, 1, matrix.rows.len() * ENTRY, BLACK);
+            }
+        }
+        for (i, (_, ones)) in matrix.rows.iter().enumerate() {
+            let y = top + i * ENTRY;
+            bitmap.draw_text(1, y, &row_names[i], 1, BLACK);
+            for j in ones {
+                let x = left + j * ENTRY;
+                if matrix.columns[*j].0 < self.constraints() {
+                    bitmap.fill_rect(x + 1, y + 1, ENTRY - 2, ENTRY - 2, BLACK);
+                } else {
+                    bitmap.frame_rect(x + 1, y + 1, ENTRY - 2, ENTRY - 2, 1, BLACK);
+                }
+            }
+        }
+        bitmap
+    }
+}
@@ -0,0 +1,294 @@
+// Puzzle booklets as PDF
+//
+// A booklet needs very little of PDF: pages of lines and text in Helvetica, which every reader
+// has, so nothing is embedded or compressed and it's all written here by hand. Puzzles are
+// grouped by difficulty, easiest first, with each group starting on a new page, and numbered
+// through the booklet. Their solutions follow in an answer key, with the givens in bold. Every
+// page has the title and section at the top, and its number at the bottom.
+//
+// Grids are drawn with `horiz_edge` and `vert_edge`, like `to_svg`, so any web can make a
+// booklet of its own puzzles.
+//
+
+use grading::{Difficulty, Puzzle};
+use SudokuWeb;
+
+// Page sizes, in points
+pub const A4: [f64; 2] = [595.0, 842.0];
+pub const LETTER: [f64; 2] = [612.0, 792.0];
+
+const MARGIN: f64 = 36.0;
+const HEADER: f64 = 30.0;
+const FOOTER: f64 = 24.0;
+const LABEL: f64 = 16.0;
+
+// How a booklet is laid out
+#[derive(Debug, Clone)]
+pub struct Booklet {
+    pub title: String,
+    pub per_page: usize,
+    pub answers_per_page: usize,
+    pub page: [f64; 2],
+}
+
+impl Default for Booklet {
+    fn default() -> Self {
+        Booklet {
+            title: "Sudoku".to_string(),
+            per_page: 4,
+            answers_per_page: 9,
+            page: A4,
+        }
+    }
+}
+
+// Roughly how wide Helvetica `text` is at `size`: every digit is 0.556 em, and most letters are
+// near enough
+fn text_width(text: &str, size: f64) -> f64 {
+    text.chars().count() as f64 * 0.556 * size
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::new();
+    for ch in text.chars() {
+        if ch == '\\' || ch == '(' || ch == ')' {
+            escaped.push('\\');
+        }
+        // Helvetica here has the WinAnsi characters, which start out like ASCII
+        escaped.push(if ch.is_ascii() { ch } else { '?' });
+    }
+    escaped
+}
+
+// A grid to draw on a page: its label, its givens, and the rest of what to fill in
+struct Slot<'a> {
+    label: String,
+    clues: &'a [[usize; 3]],
+    sol: &'a [[usize; 3]],
+}
+
+// The content of one page, in PDF's coordinates (from the bottom left)
+struct Page {
+    height: f64,
+    content: String,
+}
+
+impl Page {
+    // `bold` selects Helvetica-Bold. `y` is measured down from the top of the page, to the
+    // baseline.
+    fn text(&mut self, x: f64, y: f64, size: f64, bold: bool, text: &str) {
+        let font = if bold { "F2" } else { "F1" };
+        self.content += &format!(
+            "BT /{} {:.1} Tf {:.2} {:.2} Td ({}) Tj ET\n",
+            font,
+            size,
+            x,
+            self.height - y,
+            escape(text)
+        );
+    }
+
+    // Lines of `width`, each `[x1, y1, x2, y2]` measured down from the top of the page
+    fn lines(&mut self, width: f64, lines: &[[f64; 4]]) {
+        if lines.is_empty() {
+            return;
+        }
+        self.content += &format!("{:.2} w 2 J\n", width);
+        for [x1, y1, x2, y2] in lines.iter() {
+            self.content += &format!(
+                "{:.2} {:.2} m {:.2} {:.2} l\n",
+                x1,
+                self.height - y1,
+                x2,
+                self.height - y2
+            );
+        }
+        self.content += "S\n";
+    }
+}
+
+impl SudokuWeb {
+    // Draw a grid with its top left corner at `x`, `y`, with cells `cell` points wide. The
+    // givens are `clues`, and anything else in `sol` is filled in too.
+    fn pdf_grid(
+        &self,
+        page: &mut Page,
+        x: f64,
+        y: f64,
+        cell: f64,
+        clues: &[[usize; 3]],
+        sol: &[[usize; 3]],
+    ) {
+        let mut lines = [vec![], vec![]];
+        for r in 1..=(self.height() + 1) {
+            for c in 1..=self.width() {
+                let weight = self.horiz_edge(r, c);
+                if weight > 0 {
+                    let (x1, y1) = (x + (c - 1) as f64 * cell, y + (r - 1) as f64 * cell);
+                    lines[weight - 1].push([x1, y1, x1 + cell, y1]);
+                }
+            }
+        }
+        for r in 1..=self.height() {
+            for c in 1..=(self.width() + 1) {
+                let weight = self.vert_edge(r, c);
+                if weight > 0 {
+                    let (x1, y1) = (x + (c - 1) as f64 * cell, y + (r - 1) as f64 * cell);
+                    lines[weight - 1].push([x1, y1, x1, y1 + cell]);
+                }
+            }
+        }
+        page.lines(0.5, &lines[0]);
+        page.lines(1.5, &lines[1]);
+
+        let size = cell * 0.6;
+        let mut filled = vec![];
+        for (bold, ids) in [(true, clues), (false, sol)] {
+            for [r, c, n] in ids.iter() {
+                if filled.contains(&[*r, *c]) {
+                    continue;
+                }
+                filled.push([*r, *c]);
+                let n = n.to_string();
+                let cx = x + (*c as f64 - 0.5) * cell - text_width(&n, size) / 2.0;
+                let cy = y + (*r as f64 - 0.5) * cell + size * 0.35;
+                page.text(cx, cy, size, bold, &n);
+            }
+        }
+    }
+
+    // A page of up to `per_page` puzzles (or their solutions), each with its label
+    fn pdf_page(&self, layout: &Booklet, section: &str, per_page: usize, slots: &[Slot]) -> Page {
+        let [width, height] = layout.page;
+        let mut page = Page {
+            height,
+            content: String::new(),
+        };
+        page.text(MARGIN, MARGIN, 14.0, true, &layout.title);
+        page.text(
+            width - MARGIN - text_width(section, 12.0),
+            MARGIN,
+            12.0,
+            false,
+            section,
+        );
+
+        let per_page = per_page.max(1);
+        let across = (1..=per_page).find(|a| a * a >= per_page).unwrap_or(1);
+        let down = per_page.div_ceil(across);
+        let slot_w = (width - 2.0 * MARGIN) / across as f64;
+        let slot_h = (height - 2.0 * MARGIN - HEADER - FOOTER) / down as f64;
+        let cell = ((slot_w * 0.9) / self.width() as f64)
+            .min((slot_h - LABEL) * 0.9 / self.height() as f64);
+
+        for (i, slot) in slots.iter().enumerate() {
+            let left = MARGIN + (i % across) as f64 * slot_w;
+            let top = MARGIN + HEADER + (i / across) as f64 * slot_h;
+            let grid_w = cell * self.width() as f64;
+            let x = left + (slot_w - grid_w) / 2.0;
+            page.text(x, top + LABEL - 4.0, 10.0, true, &slot.label);
+            self.pdf_grid(&mut page, x, top + LABEL, cell, slot.clues, slot.sol);
+        }
+        page
+    }
+
+    // A booklet of `puzzles` (say, from `generate`) as a PDF file
+    pub fn booklet(&self, puzzles: &[Puzzle], layout: &Booklet) -> Vec<u8> {
+        let mut order: Vec<&Puzzle> = puzzles.iter().collect();
+        order.sort_by_key(|p| p.difficulty);
+        let label = |i: usize, difficulty: Difficulty| format!("{}. {}", i + 1, difficulty.name());
+
+        let mut pages = vec![];
+        for difficulty in Difficulty::ALL.iter() {
+            let group: Vec<Slot> = order
+                .iter()
+                .enumerate()
+                .filter(|(_, p)| p.difficulty == *difficulty)
+                .map(|(i, p)| Slot {
+                    label: label(i, p.difficulty),
+                    clues: &p.clues,
+                    sol: &[],
+                })
+                .collect();
+            for chunk in group.chunks(layout.per_page.max(1)) {
+                pages.push(self.pdf_page(layout, difficulty.name(), layout.per_page, chunk));
+            }
+        }
+
+        let answers: Vec<Slot> = order
+            .iter()
+            .enumerate()
+            .map(|(i, p)| Slot {
+                label: label(i, p.difficulty),
+                clues: &p.clues,
+                sol: &p.solution,
+            })
+            .collect();
+        for chunk in answers.chunks(layout.answers_per_page.max(1)) {
+            pages.push(self.pdf_page(layout, "Answers", layout.answers_per_page, chunk));
+        }
+
+        let count = pages.len();
+        for (i, page) in pages.iter_mut().enumerate() {
+            let number = format!("{} / {}", i + 1, count);
+            let x = (layout.page[0] - text_width(&number, 10.0)) / 2.0;
+            page.text(x, layout.page[1] - MARGIN / 2.0, 10.0, false, &number);
+        }
+        write_pdf(layout.page, &pages)
+    }
+}
+
+// The catalog, the page tree, and the two fonts are objects 1 to 4. Each page is then an object
+// followed by its content stream.
+fn write_pdf([width, height]: [f64; 2], pages: &[Page]) -> Vec<u8> {
+    let mut objects = vec![
+        "<< /Type /Catalog /Pages 2 0 R >>".to_string(),
+        String::new(),
+        "<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica /Encoding /WinAnsiEncoding >>"
+            .to_string(),
+        "<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica-Bold /Encoding /WinAnsiEncoding >>"
+            .to_string(),
+    ];
+    let mut kids = vec![];
+    for page in pages {
+        let id = objects.len() + 1;
+        kids.push(format!("{} 0 R", id));
+        objects.push(format!(
+            concat!(
+                "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {} {}] ",
+                "/Resources << /Font << /F1 3 0 R /F2 4 0 R >> >> /Contents {} 0 R >>"
+            ),
+            width,
+            height,
+            id + 1
+        ));
+        objects.push(format!(
+            "<< /Length {} >>\nstream\n{}endstream",
+            page.content.len(),
+            page.content
+        ));
+    }
+    objects[1] = format!(
+        "<< /Type /Pages /Kids [{}] /Count {} >>",
+        kids.join(" "),
+        pages.len()
+    );
+
+    let mut pdf = "%PDF-1.4\n".to_string();
+    let mut offsets = vec![];
+    for (i, object) in objects.iter().enumerate() {
+        offsets.push(pdf.len());
+        pdf += &format!("{} 0 obj\n{}\nendobj\n", i + 1, object);
+    }
+    let xref = pdf.len();
+    pdf += &format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1);
+    for offset in offsets {
+        pdf += &format!("{:010} 00000 n \n", offset);
+    }
+    pdf += &format!(
+        "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n",
+        objects.len() + 1,
+        xref
+    );
+    pdf.into_bytes()
+}
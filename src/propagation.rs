@@ -0,0 +1,236 @@
+// A constraint propagation and backtracking solver, for puzzles with rules
+//
+// `dance` only hears from a rule about the rows it could choose next, so sums and chains of
+// inequalities are mostly discovered by trial and error. This solver instead keeps a domain for
+// every cell (see `Rule::narrow`), and before every guess narrows them all as far as it can:
+//
+// - A cell with one symbol left is placed, which removes that symbol from every cell sharing one
+//   of its columns (cells, rows, columns, boxes, and secondary columns alike)
+// - A primary column with only one of its rows left must have that row
+// - Every rule narrows the domains as it sees fit, and then drops what it doesn't admit
+//
+// Then it guesses one of the symbols left in a cell with the fewest of them, keeping a copy of the
+// domains to back up to.
+//
+
+use limits::{Budget, Outcome};
+use rand::{self, Rng};
+use rules::{span, symbols_in, Board};
+use {Knowing, SudokuWeb};
+
+#[derive(Debug, Clone)]
+struct State {
+    domains: Vec<u64>,
+    board: Board,
+}
+
+struct Propagator<'a> {
+    web: &'a SudokuWeb,
+    // The board index of every cell of every grid
+    cells: Vec<usize>,
+    // The `(board index, n)` of each row in each column, primary columns first
+    columns: Vec<Vec<(usize, usize)>>,
+    seek: usize,
+    print: bool,
+    rand: bool,
+    budget: Budget,
+    solutions: usize,
+    solution: Vec<[usize; 3]>,
+}
+
+impl<'a> Propagator<'a> {
+    fn cell_of(&self, i: usize) -> [usize; 2] {
+        let width = self.web.width();
+        [i / width + 1, i % width + 1]
+    }
+
+    fn admits(&self, board: &Board, id: [usize; 3]) -> bool {
+        self.web.rules.iter().all(|rule| rule.admits(board, id))
+    }
+
+    // Narrow every domain as far as it goes. Returns false if some cell can't be filled.
+    fn propagate(&self, state: &mut State) -> bool {
+        loop {
+            let before = state.domains.clone();
+
+            for i in self.cells.iter().copied() {
+                let domain = state.domains[i];
+                let [r, c] = self.cell_of(i);
+                if domain.count_ones() != 1 || state.board.get(r, c) != 0 {
+                    continue;
+                }
+                let n = domain.trailing_zeros() as usize + 1;
+                state.board.set(r, c, n);
+                for column in self.web.indices_from_rcn(r, c, n) {
+                    for (j, m) in self.columns[column].iter().copied() {
+                        if j != i {
+                            state.domains[j] &= !(1 << (m - 1));
+                        }
+                    }
+                }
+            }
+
+            for column in self.columns[..self.web.constraints()].iter() {
+                let mut left = column
+                    .iter()
+                    .filter(|(j, m)| state.domains[*j] & 1 << (m - 1) != 0);
+                match (left.next(), left.next()) {
+                    (None, _) => return false,
+                    (Some((j, m)), None) => state.domains[*j] = 1 << (m - 1),
+                    _ => (),
+                }
+            }
+
+            for rule in self.web.rules.iter() {
+                rule.narrow(&state.board, &mut state.domains);
+            }
+            // Placed cells are asked again too, about what has been placed since
+            for i in self.cells.iter().copied() {
+                let [r, c] = self.cell_of(i);
+                for n in symbols_in(state.domains[i]) {
+                    if !self.admits(&state.board, [r, c, n]) {
+                        state.domains[i] &= !(1 << (n - 1));
+                    }
+                }
+            }
+
+            if self.cells.iter().any(|i| state.domains[*i] == 0) {
+                return false;
+            }
+            if state.domains == before {
+                return true;
+            }
+        }
+    }
+
+    // Returns whether to keep looking
+    fn search(&mut self, mut state: State) -> bool {
+        if !self.budget.spend() {
+            return false;
+        }
+        if !self.propagate(&mut state) {
+            return true;
+        }
+
+        let guess = self
+            .cells
+            .iter()
+            .copied()
+            .filter(|i| state.domains[*i].count_ones() > 1)
+            .min_by_key(|i| state.domains[*i].count_ones());
+        let i = match guess {
+            Some(i) => i,
+            None => return self.found(&state),
+        };
+
+        let mut symbols: Vec<usize> = symbols_in(state.domains[i]).collect();
+        if self.rand {
+            rand::thread_rng().shuffle(&mut symbols);
+        }
+        for n in symbols {
+            let mut next = state.clone();
+            next.domains[i] = 1 << (n - 1);
+            if !self.search(next) {
+                return false;
+            }
+        }
+        true
+    }
+
+    fn found(&mut self, state: &State) -> bool {
+        self.solutions += 1;
+        let mut sol = self.web.prop_solution.clone();
+        for i in self.cells.iter() {
+            let [r, c] = self.cell_of(*i);
+            let id = [r, c, state.board.get(r, c)];
+            if !sol.contains(&id) {
+                sol.push(id);
+            }
+        }
+        if self.print {
+            println!("[{}]: Solution found:", self.solutions);
+            self.web.print_solution(&sol);
+        }
+        self.solution = sol;
+        self.solutions < self.seek
+    }
+}
+
+impl SudokuWeb {
+    // Like `solve`, but by propagating constraints instead of dancing
+    pub fn propagate(&mut self, seek: usize, print: bool, rand: bool) -> Outcome {
+        let nums = self.symbols();
+        let mut domains = vec![0; self.height() * self.width()];
+        let cells: Vec<usize> = self
+            .cells()
+            .iter()
+            .map(|[r, c]| (r - 1) * self.width() + (c - 1))
+            .collect();
+        let candidates = self.prop_candidates.iter().chain(std::iter::repeat(&!0));
+        for ([r, c], candidates) in self.cells().into_iter().zip(candidates) {
+            domains[(r - 1) * self.width() + (c - 1)] =
+                self.allowed_at(r, c) & candidates & span(1, nums);
+        }
+
+        let mut possible = true;
+        for [r, c, n] in self.prop_solution.iter() {
+            let i = (r - 1) * self.width() + (c - 1);
+            if !cells.contains(&i) || domains[i] & 1 << (n - 1) == 0 {
+                possible = false;
+            }
+            domains[i] &= 1 << (n - 1);
+        }
+
+        let columns = self
+            .rows_of_columns()
+            .iter()
+            .map(|rows| {
+                rows.iter()
+                    .map(|[r, c, n]| ((r - 1) * self.width() + (c - 1), *n))
+                    .collect()
+            })
+            .collect();
+        let mut propagator = Propagator {
+            web: self,
+            cells,
+            columns,
+            seek,
+            print,
+            rand,
+            budget: Budget::new(&self.limits),
+            solutions: 0,
+            solution: vec![],
+        };
+        if possible {
+            let board = Board::new(self.height(), self.width(), nums);
+            propagator.search(State { domains, board });
+        }
+
+        let (solutions, solution) = (propagator.solutions, propagator.solution);
+        let outcome = propagator.budget.outcome;
+        self.solution_count = solutions;
+        self.solution = solution;
+        if outcome.limit_reached() {
+            self.possible = if solutions > 0 {
+                Knowing::Yes
+            } else {
+                Knowing::Unknown
+            };
+            if solutions > 1 {
+                self.uniq = Knowing::No;
+            } else {
+                self.uniq = Knowing::Unknown;
+            }
+        } else if solutions == 0 {
+            self.possible = Knowing::No;
+        } else {
+            self.possible = Knowing::Yes;
+            if solutions > 1 {
+                self.uniq = Knowing::No;
+            } else if seek > 1 {
+                self.uniq = Knowing::Yes;
+            }
+        }
+        outcome
+    }
+}
@@ -0,0 +1,133 @@
+// Puzzles and solutions as pictures, drawn in-process (see the `bitmap` module), for thumbnails
+// and chat replies
+//
+// The board is drawn like `to_svg` draws it: thick box borders and board edges, thin lines
+// between other cells, givens (`prop_solution`) in bold, and the rest of a solution in another
+// color. Some cells can be highlighted, say the one a hint is about.
+//
+
+use bitmap::{Bitmap, Color, BLACK, WHITE};
+use SudokuWeb;
+
+#[derive(Debug, Clone)]
+pub struct RasterStyle {
+    // The pixels on each side of a cell, and around the board
+    pub cell: usize,
+    pub margin: usize,
+    // The pixels on each side of a pixel of the font (see the `font` module), 5 by 7 of them to a
+    // digit
+    pub font_scale: usize,
+    pub given: Color,
+    pub solved: Color,
+    pub highlight: Color,
+    pub thin: Color,
+}
+
+impl Default for RasterStyle {
+    fn default() -> Self {
+        RasterStyle {
+            cell: 32,
+            margin: 8,
+            font_scale: 3,
+            given: BLACK,
+            solved: [42, 93, 176],
+            highlight: [255, 236, 139],
+            thin: [153, 153, 153],
+        }
+    }
+}
+
+impl SudokuWeb {
+    // `sol`: what to fill in, besides the givens (which needn't be repeated)
+    // `highlights`: the `[r, c]` cells to pick out
+    pub fn to_bitmap(
+        &self,
+        sol: &[[usize; 3]],
+        style: &RasterStyle,
+        highlights: &[[usize; 2]],
+    ) -> Bitmap {
+        let (cell, margin) = (style.cell, style.margin);
+        let mut bitmap = Bitmap::new(
+            2 * margin + self.width() * cell,
+            2 * margin + self.height() * cell,
+            WHITE,
+        );
+        let left = |c: usize| margin + (c - 1) * cell;
+        let top = |r: usize| margin + (r - 1) * cell;
+
+        for [r, c] in highlights.iter() {
+            if self.in_board(*r, *c) {
+                bitmap.fill_rect(left(*c), top(*r), cell, cell, style.highlight);
+            }
+        }
+
+        // Thin edges first, so thick ones cover their ends
+        for weight in 1..=2 {
+            let (width, color) = if weight == 1 {
+                (1, style.thin)
+            } else {
+                (3, BLACK)
+            };
+            for r in 1..=(self.height() + 1) {
+                for c in 1..=self.width() {
+                    if self.horiz_edge(r, c) == weight {
+                        let (x, y) = (left(c), top(r));
+                        bitmap.fill_rect(
+                            x.saturating_sub(width / 2),
+                            y.saturating_sub(width / 2),
+                            cell + width,
+                            width,
+                            color,
+                        );
+                    }
+                }
+            }
+            for r in 1..=self.height() {
+                for c in 1..=(self.width() + 1) {
+                    if self.vert_edge(r, c) == weight {
+                        let (x, y) = (left(c), top(r));
+                        bitmap.fill_rect(
+                            x.saturating_sub(width / 2),
+                            y.saturating_sub(width / 2),
+                            width,
+                            cell + width,
+                            color,
+                        );
+                    }
+                }
+            }
+        }
+
+        let scale = style.font_scale;
+        let mut filled = vec![];
+        for (bold, ids) in [(true, &self.prop_solution[..]), (false, sol)] {
+            for [r, c, n] in ids.iter() {
+                if filled.contains(&[*r, *c]) {
+                    continue;
+                }
+                filled.push([*r, *c]);
+                let n = n.to_string();
+                let x = left(*c) + cell.saturating_sub(Bitmap::text_width(&n, scale)) / 2;
+                let y = top(*r) + cell.saturating_sub(Bitmap::text_height(scale)) / 2;
+                if bold {
+                    // Bold is the digit drawn twice, half a font pixel apart
+                    bitmap.draw_text(x, y, &n, scale, style.given);
+                    bitmap.draw_text(x + scale.div_ceil(2), y, &n, scale, style.given);
+                } else {
+                    bitmap.draw_text(x, y, &n, scale, style.solved);
+                }
+            }
+        }
+        bitmap
+    }
+
+    // `to_bitmap` as a PNG file
+    pub fn to_png(
+        &self,
+        sol: &[[usize; 3]],
+        style: &RasterStyle,
+        highlights: &[[usize; 2]],
+    ) -> Vec<u8> {
+        self.to_bitmap(sol, style, highlights).to_png()
+    }
+}
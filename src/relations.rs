@@ -0,0 +1,227 @@
+// Relations between neighboring cells: greater-than signs, Kropki dots, and XV
+//
+// Marks are read from a text description, one per line:
+//
+//     # Anything after a `#` is a comment
+//     r1c1 < r1c2    # r1c1 is less than r1c2 (`>` works too)
+//     r2c3 w r2c4    # a white dot: the two are consecutive
+//     r2c3 b r3c3    # a black dot: one is double the other
+//     r5c5 x r5c6    # the two add up to 10
+//     r5c5 v r6c5    # the two add up to 5
+//     negative w b   # no two neighbors without a mark are consecutive or doubled
+//
+// The `negative` line takes any of `w`, `b`, `x`, and `v`, and says that every mark of those
+// kinds has been given: neighbors without one of those marks between them don't have that
+// relation.
+//
+
+use rules::{symbols_in, Board, Rule};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mark {
+    // The first cell is less than the second
+    Less,
+    // The two are consecutive
+    White,
+    // One is double the other
+    Black,
+    // The two add up to 10
+    X,
+    // The two add up to 5
+    V,
+}
+
+impl Mark {
+    fn parse(s: &str) -> Option<Self> {
+        match s {
+            "<" => Some(Mark::Less),
+            "w" | "W" => Some(Mark::White),
+            "b" | "B" => Some(Mark::Black),
+            "x" | "X" => Some(Mark::X),
+            "v" | "V" => Some(Mark::V),
+            _ => None,
+        }
+    }
+
+    // Do `a` (in the first cell) and `b` (in the second) have this relation?
+    fn holds(self, a: usize, b: usize) -> bool {
+        match self {
+            Mark::Less => a < b,
+            Mark::White => a + 1 == b || b + 1 == a,
+            Mark::Black => a == 2 * b || b == 2 * a,
+            Mark::X => a + b == 10,
+            Mark::V => a + b == 5,
+        }
+    }
+}
+
+// Every mark on the board, and the kinds that are negative constraints
+#[derive(Debug, Clone, Default)]
+pub struct Relations {
+    pub marks: Vec<([usize; 2], Mark, [usize; 2])>,
+    pub negative: Vec<Mark>,
+}
+
+impl Relations {
+    // Read the marks for a board `height` cells tall and `width` wide
+    pub fn parse(text: &str, height: usize, width: usize) -> Result<Self, String> {
+        let mut relations = Relations::default();
+        for (line_i, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or("");
+            let words: Vec<&str> = line.split_whitespace().collect();
+            let bad = |why: &str| Err(format!("line {}: {}: {}", line_i + 1, why, line.trim()));
+            match words.as_slice() {
+                [] => (),
+                ["negative", kinds @ ..] => {
+                    for kind in kinds {
+                        match Mark::parse(kind) {
+                            Some(Mark::Less) | None => return bad("not a negative constraint"),
+                            Some(mark) => relations.negative.push(mark),
+                        }
+                    }
+                }
+                [a, mark, b] => {
+                    let (a, b) = match (parse_cell(a), parse_cell(b)) {
+                        (Some(a), Some(b)) => (a, b),
+                        _ => return bad("cells look like r1c2"),
+                    };
+                    if !on_board(a, height, width) || !on_board(b, height, width) {
+                        return bad("the cells must be on the board");
+                    }
+                    if a[0].max(b[0]) - a[0].min(b[0]) + a[1].max(b[1]) - a[1].min(b[1]) != 1 {
+                        return bad("the cells are not neighbors");
+                    }
+                    match (*mark, Mark::parse(mark)) {
+                        (">", _) => relations.marks.push((b, Mark::Less, a)),
+                        (_, Some(m)) => relations.marks.push((a, m, b)),
+                        (_, None) => return bad("unknown mark"),
+                    }
+                }
+                _ => return bad("expected `<cell> <mark> <cell>` or `negative <marks>`"),
+            }
+        }
+        Ok(relations)
+    }
+
+    // Is there a mark of this kind between `a` and `b`?
+    fn marked(&self, a: [usize; 2], b: [usize; 2], kind: Mark) -> bool {
+        self.marks.iter().any(|(m_a, m, m_b)| {
+            *m == kind && ((*m_a == a && *m_b == b) || (*m_a == b && *m_b == a))
+        })
+    }
+
+    // Do `n` and `m`, in neighbors `a` and `b`, break a negative constraint? Only the kinds
+    // without a mark between the two apply.
+    fn breaks_negative(&self, a: [usize; 2], b: [usize; 2], n: usize, m: usize) -> bool {
+        self.negative
+            .iter()
+            .any(|mark| mark.holds(n, m) && !self.marked(a, b, *mark))
+    }
+}
+
+// Keep only the symbols `a` may hold that go with one `b` may hold
+fn revise(
+    board: &Board,
+    domains: &mut [u64],
+    a: [usize; 2],
+    b: [usize; 2],
+    goes: impl Fn(usize, usize) -> bool,
+) {
+    if !board.contains(a[0], a[1]) || !board.contains(b[0], b[1]) {
+        return;
+    }
+    let (a_i, b_i) = (board.index(a[0], a[1]), board.index(b[0], b[1]));
+    if domains[b_i] == 0 {
+        return;
+    }
+    domains[a_i] = symbols_in(domains[a_i])
+        .filter(|n| symbols_in(domains[b_i]).any(|m| goes(*n, m)))
+        .fold(0, |domain, n| domain | 1 << (n - 1));
+}
+
+impl Rule for Relations {
+    fn admits(&self, board: &Board, id: [usize; 3]) -> bool {
+        let [r, c, n] = id;
+        let at = [r, c];
+
+        for (a, mark, b) in self.marks.iter() {
+            let ok = if *a == at {
+                let other = board.get(b[0], b[1]);
+                other == 0 || mark.holds(n, other)
+            } else if *b == at {
+                let other = board.get(a[0], a[1]);
+                other == 0 || mark.holds(other, n)
+            } else {
+                true
+            };
+            if !ok {
+                return false;
+            }
+        }
+
+        if self.negative.is_empty() {
+            return true;
+        }
+        neighbors(r, c).iter().all(|[nr, nc]| {
+            let other = board.get(*nr, *nc);
+            other == 0 || !self.breaks_negative(at, [*nr, *nc], n, other)
+        })
+    }
+
+    fn narrow(&self, board: &Board, domains: &mut [u64]) {
+        for (a, mark, b) in self.marks.iter() {
+            revise(board, domains, *a, *b, |n, m| mark.holds(n, m));
+            revise(board, domains, *b, *a, |n, m| mark.holds(m, n));
+        }
+
+        if self.negative.is_empty() {
+            return;
+        }
+        for r in 1..=board.height() {
+            for c in 1..=board.width() {
+                for other in neighbors(r, c).iter() {
+                    revise(board, domains, [r, c], *other, |n, m| {
+                        !self.breaks_negative([r, c], *other, n, m)
+                    });
+                }
+            }
+        }
+    }
+
+    fn watches(&self, _board: &Board, r: usize, c: usize) -> Vec<[usize; 2]> {
+        if !self.negative.is_empty() {
+            return neighbors(r, c).to_vec();
+        }
+        self.marks
+            .iter()
+            .filter_map(|(a, _, b)| match () {
+                _ if *a == [r, c] => Some(*b),
+                _ if *b == [r, c] => Some(*a),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+// The cells above, below, left, and right of `r`, `c` (which may be off of the board)
+fn neighbors(r: usize, c: usize) -> [[usize; 2]; 4] {
+    [[r - 1, c], [r + 1, c], [r, c - 1], [r, c + 1]]
+}
+
+// Is `cell` on a board `height` cells tall and `width` wide?
+pub fn on_board(cell: [usize; 2], height: usize, width: usize) -> bool {
+    cell[0] <= height && cell[1] <= width
+}
+
+// Read a cell like `r1c2`
+pub fn parse_cell(s: &str) -> Option<[usize; 2]> {
+    let s = s.to_lowercase();
+    let rest = s.strip_prefix('r')?;
+    let c_i = rest.find('c')?;
+    let r = rest[..c_i].parse().ok()?;
+    let c = rest[c_i + 1..].parse().ok()?;
+    if r == 0 || c == 0 {
+        return None;
+    }
+    Some([r, c])
+}
@@ -0,0 +1,314 @@
+// Drawing grids as text, anywhere: to a `String` (through `Display`), or to any `io::Write`
+//
+// `fmt_solution` (and `fmt_board`, for boards of several grids) do the drawing, in box-drawing
+// characters or, for terminals without them, in plain ASCII:
+//
+//     +==+==+==+==+
+//     | 1: 2| 3: 4|
+//     +--+--+--+--+
+//
+// With pencil marks, `fmt_marks` draws each cell big enough for a little grid of them.
+//
+// `print_solution` is just a `Grid` printed to stdout, in color if stdout is a terminal: the
+// givens bold, the cells filled in since in cyan, and any cell in conflict with another (or with
+// a rule) in red.
+//
+
+use rules::Board;
+use std::env;
+use std::fmt;
+use std::io::{self, IsTerminal};
+use SudokuWeb;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Charset {
+    Unicode,
+    Ascii,
+}
+
+impl Charset {
+    // The lines across a single grid, as `fmt_horiz_line` takes them: above the first row, above
+    // the first row of a box, above any other row, and below the last row
+    pub fn top(self) -> [char; 5] {
+        match self {
+            Charset::Unicode => ['╔', '╗', '╦', '╤', '═'],
+            Charset::Ascii => ['+', '+', '+', '+', '='],
+        }
+    }
+
+    pub fn thick(self) -> [char; 5] {
+        match self {
+            Charset::Unicode => ['╠', '╣', '╬', '╪', '═'],
+            Charset::Ascii => ['+', '+', '+', '+', '='],
+        }
+    }
+
+    pub fn thin(self) -> [char; 5] {
+        match self {
+            Charset::Unicode => ['╟', '╢', '╫', '┼', '─'],
+            Charset::Ascii => ['+', '+', '+', '+', '-'],
+        }
+    }
+
+    pub fn bottom(self) -> [char; 5] {
+        match self {
+            Charset::Unicode => ['╚', '╝', '╩', '╧', '═'],
+            Charset::Ascii => ['+', '+', '+', '+', '='],
+        }
+    }
+
+    // A line down the left of a cell, on a box border or not
+    pub fn vert(self, thick: bool) -> char {
+        match (self, thick) {
+            (Charset::Unicode, true) => '║',
+            (Charset::Unicode, false) => '│',
+            (Charset::Ascii, true) => '|',
+            (Charset::Ascii, false) => ':',
+        }
+    }
+
+    // A line across, of the weight `horiz_edge` gives
+    pub fn horiz(self, weight: usize) -> char {
+        match (self, weight) {
+            (_, 0) => ' ',
+            (Charset::Unicode, 1) => '─',
+            (Charset::Unicode, _) => '═',
+            (Charset::Ascii, 1) => '-',
+            (Charset::Ascii, _) => '=',
+        }
+    }
+
+    // One of the box-drawing characters `junction` picks, in this charset
+    pub fn junction(self, ch: char) -> char {
+        match (self, ch) {
+            (Charset::Unicode, _) | (_, ' ') => ch,
+            (Charset::Ascii, _) => '+',
+        }
+    }
+}
+
+// Which cells to paint how, when drawing in color. Without `color`, only the cursor is shown.
+pub struct Colors {
+    givens: Vec<[usize; 2]>,
+    conflicts: Vec<[usize; 2]>,
+    cursor: Option<[usize; 2]>,
+    color: bool,
+}
+
+// `text`, the symbol at `r`, `c`, right-aligned in `width` columns, and painted if there are
+// `colors`. The padding is left unpainted, so it lines up either way, except at the cursor, which
+// is shown in reverse video whether or not it holds anything.
+pub fn paint(colors: Option<&Colors>, r: usize, c: usize, text: &str, width: usize) -> String {
+    let pad = " ".repeat(width.saturating_sub(text.chars().count()));
+    let colors = match colors {
+        Some(colors) => colors,
+        None => return pad + text,
+    };
+    let at_cursor = colors.cursor == Some([r, c]);
+    let given = colors.givens.contains(&[r, c]);
+    let code = match (colors.color, colors.conflicts.contains(&[r, c]), given) {
+        (false, _, _) => "0",
+        (true, true, true) => "1;31",
+        (true, true, false) => "31",
+        (true, false, true) => "1",
+        (true, false, false) => "36",
+    };
+    match (at_cursor, text.is_empty()) {
+        (true, true) => format!("\x1b[7m{}\x1b[0m", pad),
+        (true, false) => format!("\x1b[{};7m{}{}\x1b[0m", code, pad, text),
+        (false, true) => pad,
+        (false, false) if !colors.color => pad + text,
+        (false, false) => format!("{}\x1b[{}m{}\x1b[0m", pad, code, text),
+    }
+}
+
+// `text` drawn over the cell at `r`, `c`, in reverse video if it's at the cursor
+pub fn highlight(colors: Option<&Colors>, r: usize, c: usize, text: &str) -> String {
+    match colors {
+        Some(colors) if colors.cursor == Some([r, c]) && !text.is_empty() => {
+            format!("\x1b[7m{}\x1b[0m", text)
+        }
+        _ => text.to_string(),
+    }
+}
+
+// Should what's printed to stdout be in color? Not when it's going to a file or a pipe, nor when
+// `NO_COLOR` is set.
+pub fn color_stdout() -> bool {
+    io::stdout().is_terminal() && env::var_os("NO_COLOR").is_none()
+}
+
+// A solution drawn on a web's grid, for `Display`
+pub struct Grid<'a> {
+    web: &'a SudokuWeb,
+    sol: &'a [[usize; 3]],
+    charset: Charset,
+    color: bool,
+    marks: Option<&'a [u64]>,
+    cursor: Option<[usize; 2]>,
+    flagged: &'a [[usize; 2]],
+}
+
+impl<'a> Grid<'a> {
+    pub fn ascii(self) -> Self {
+        Grid {
+            charset: Charset::Ascii,
+            ..self
+        }
+    }
+
+    pub fn charset(self, charset: Charset) -> Self {
+        Grid { charset, ..self }
+    }
+
+    // In ANSI colors (see `color_stdout` for whether a terminal wants them)
+    pub fn color(self, color: bool) -> Self {
+        Grid { color, ..self }
+    }
+
+    // With pencil marks in the empty cells: a bit mask for each of `cells()`, like
+    // `prop_candidates` (or `candidates`)
+    pub fn marks(self, marks: &'a [u64]) -> Self {
+        Grid {
+            marks: Some(marks),
+            ..self
+        }
+    }
+
+    // With the cell at `cursor` picked out, in reverse video (which is all that's drawn that way
+    // when not in color)
+    pub fn cursor(self, cursor: [usize; 2]) -> Self {
+        Grid {
+            cursor: Some(cursor),
+            ..self
+        }
+    }
+
+    // With `flagged` cells shown in red like conflicts, when in color (say, wrong entries)
+    pub fn flag(self, flagged: &'a [[usize; 2]]) -> Self {
+        Grid { flagged, ..self }
+    }
+}
+
+impl<'a> fmt::Display for Grid<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let colors = if self.color {
+            let mut colors = self.web.colors(self.sol);
+            colors.conflicts.extend(self.flagged);
+            colors.cursor = self.cursor;
+            Some(colors)
+        } else if self.cursor.is_some() {
+            Some(Colors {
+                givens: vec![],
+                conflicts: vec![],
+                cursor: self.cursor,
+                color: false,
+            })
+        } else {
+            None
+        };
+        match self.marks {
+            Some(marks) => self
+                .web
+                .fmt_marks(f, self.sol, marks, self.charset, colors.as_ref()),
+            None => self
+                .web
+                .fmt_solution(f, self.sol, self.charset, colors.as_ref()),
+        }
+    }
+}
+
+// A web shows its puzzle: the clues in `prop_solution`
+impl fmt::Display for SudokuWeb {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.grid(&self.prop_solution).fmt(f)
+    }
+}
+
+impl SudokuWeb {
+    // `sol` on this web's grid, in box-drawing characters until told otherwise
+    pub fn grid<'a>(&'a self, sol: &'a [[usize; 3]]) -> Grid<'a> {
+        Grid {
+            web: self,
+            sol,
+            charset: Charset::Unicode,
+            color: false,
+            marks: None,
+            cursor: None,
+            flagged: &[],
+        }
+    }
+
+    // The cells of `sol` that can't stand as they are: off the board, holding something they
+    // can't, clashing with a given, sharing a column (a row, a box, or any other) with another
+    // cell of the same symbol, or not admitted by a rule given the rest
+    pub fn conflicts(&self, sol: &[[usize; 3]]) -> Vec<[usize; 2]> {
+        let mut conflicts = vec![];
+        let mut placed = vec![];
+        for [r, c, n] in sol.iter().copied() {
+            let fits = r >= 1
+                && c >= 1
+                && r <= self.height()
+                && c <= self.width()
+                && self.in_grid(r, c)
+                && n >= 1
+                && n <= self.symbols()
+                && self.allows(r, c, n);
+            let clashes = self
+                .prop_solution
+                .iter()
+                .any(|[gr, gc, gn]| [*gr, *gc] == [r, c] && *gn != n);
+            if fits && !clashes {
+                placed.push([r, c, n]);
+            } else {
+                conflicts.push([r, c]);
+            }
+        }
+
+        let mut seen: Vec<Vec<[usize; 2]>> = vec![vec![]; self.columns()];
+        for [r, c, n] in placed.iter().copied() {
+            for i in self.indices_from_rcn(r, c, n) {
+                seen[i].push([r, c]);
+            }
+        }
+        for cells in seen.into_iter().filter(|cells| cells.len() > 1) {
+            conflicts.extend(cells);
+        }
+
+        let mut board = Board::with_solution(self.height(), self.width(), self.symbols(), &placed);
+        for [r, c, n] in placed.iter().copied() {
+            board.set(r, c, 0);
+            if !self.rules.iter().all(|rule| rule.admits(&board, [r, c, n])) {
+                conflicts.push([r, c]);
+            }
+            board.set(r, c, n);
+        }
+
+        conflicts.sort_unstable();
+        conflicts.dedup();
+        conflicts
+    }
+
+    // The givens of `sol` (the cells it shares with `prop_solution`), and its conflicts
+    fn colors(&self, sol: &[[usize; 3]]) -> Colors {
+        Colors {
+            givens: sol
+                .iter()
+                .filter(|id| self.prop_solution.contains(id))
+                .map(|[r, c, _]| [*r, *c])
+                .collect(),
+            conflicts: self.conflicts(sol),
+            cursor: None,
+            color: true,
+        }
+    }
+
+    pub fn write_solution(
+        &self,
+        out: &mut dyn io::Write,
+        sol: &[[usize; 3]],
+        charset: Charset,
+    ) -> io::Result<()> {
+        write!(out, "{}", self.grid(sol).charset(charset))
+    }
+}
@@ -0,0 +1,126 @@
+// Rules that can't be expressed as exact cover
+//
+// Exact cover can say "exactly one of these rows" (a column) or "at most one of these rows" (a
+// secondary column), but not "these two cells differ by one" or "this cell is the sum of those".
+// So `dance` keeps a `Board` of what it has placed so far, and asks every `Rule` before it
+// chooses each row. A row a rule doesn't admit is skipped, as if it had already been covered.
+//
+// Skipped rows would still count towards their columns' sizes, and `dance` could go a long way
+// before finding a cell with nothing left it may hold. So after placing a row it also removes the
+// rows the rules no longer admit from every empty cell they `watch` from there, until it backs up.
+//
+
+use std::fmt;
+
+// The symbols (1 through `symbols`) placed so far, by `[r, c]` (counting from 1). Empty cells
+// hold 0.
+#[derive(Debug, Clone)]
+pub struct Board {
+    height: usize,
+    width: usize,
+    symbols: usize,
+    cells: Vec<usize>,
+}
+
+impl Board {
+    pub fn new(height: usize, width: usize, symbols: usize) -> Self {
+        Board {
+            height,
+            width,
+            symbols,
+            cells: vec![0; height * width],
+        }
+    }
+
+    // A board with every `[r, c, n]` of `solution` placed on it
+    pub fn with_solution(height: usize, width: usize, symbols: usize, sol: &[[usize; 3]]) -> Self {
+        let mut board = Board::new(height, width, symbols);
+        for [r, c, n] in sol.iter() {
+            board.set(*r, *c, *n);
+        }
+        board
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn symbols(&self) -> usize {
+        self.symbols
+    }
+
+    pub fn contains(&self, r: usize, c: usize) -> bool {
+        r >= 1 && c >= 1 && r <= self.height && c <= self.width
+    }
+
+    // Where the cell at `r`, `c` is in anything laid out like the board, top to bottom, then left
+    // to right (like the domains `Rule::narrow` is given)
+    pub fn index(&self, r: usize, c: usize) -> usize {
+        (r - 1) * self.width + (c - 1)
+    }
+
+    // The symbol at `r`, `c`, or 0 if it's empty (or off of the board)
+    pub fn get(&self, r: usize, c: usize) -> usize {
+        if !self.contains(r, c) {
+            return 0;
+        }
+        self.cells[self.index(r, c)]
+    }
+
+    pub fn set(&mut self, r: usize, c: usize, n: usize) {
+        let i = self.index(r, c);
+        self.cells[i] = n;
+    }
+}
+
+pub trait Rule: fmt::Debug {
+    // Could `[r, c, n]` be placed, given what's already on the board? This only needs to catch
+    // what's already certain to break the rule; it will be asked again as more is placed.
+    fn admits(&self, board: &Board, id: [usize; 3]) -> bool;
+
+    // The cells whose rows might stop being admitted once something is placed at `r`, `c`
+    fn watches(&self, board: &Board, r: usize, c: usize) -> Vec<[usize; 2]>;
+
+    // Narrow `domains` to what this rule still allows. There is a domain for each cell (laid out
+    // like the board; see `Board::index`), a bit mask with bit `n - 1` set if the cell may still
+    // hold `n`. An empty domain means the cell is off of every grid, or can't be filled at all;
+    // either way, other cells needn't be narrowed on its account.
+    //
+    // This is only a shortcut for the propagating solver: anything it removes must be something
+    // `admits` would eventually reject. So by default it removes nothing.
+    //
+    fn narrow(&self, _board: &Board, _domains: &mut [u64]) {}
+
+    // The cages this rule draws on the board (as in killer sudoku), each with its total, for
+    // renderers to outline. Most rules have none.
+    fn cages(&self) -> Vec<(Vec<[usize; 2]>, usize)> {
+        vec![]
+    }
+}
+
+// The symbols in a domain (see `Rule::narrow`), smallest first
+pub fn symbols_in(domain: u64) -> impl Iterator<Item = usize> {
+    (1..=64).filter(move |n| domain & 1 << (n - 1) != 0)
+}
+
+// The smallest and largest symbols in a (non-empty) domain
+pub fn lowest(domain: u64) -> usize {
+    domain.trailing_zeros() as usize + 1
+}
+
+pub fn highest(domain: u64) -> usize {
+    64 - domain.leading_zeros() as usize
+}
+
+// The domain of every symbol from `low` through `high`
+pub fn span(low: usize, high: usize) -> u64 {
+    let (low, high) = (low.max(1), high.min(64));
+    if low > high {
+        return 0;
+    }
+    (!0 >> (64 - (high - low + 1))) << (low - 1)
+}
@@ -0,0 +1,232 @@
+// Games saved to files, to pick up again later
+//
+// A session is text, a line for each thing saved. The first line names the format and its
+// version; every other line is a key and its values, separated by spaces:
+//
+//     sudoku session 1
+//     boxes 3 3
+//     difficulty Hard
+//     elapsed 754.250
+//     hints 1
+//     cursor 4 5
+//     pencil no
+//     clue 1 4 8
+//     entry 2 3 5
+//     marks 2 4 73
+//     undo 2 3 0 0 5 0
+//     redo 6 6 0 4 0 0
+//
+// Clues, entries, and pencil marks (a bit mask, like `prop_candidates`) are given for one cell
+// each, at its row and column. `undo` and `redo` are the moves on those stacks, bottom first,
+// each a cell and what it held before and after: entry then marks. The elapsed time is in
+// seconds. Only `boxes` is required, so a session can be written by hand, too.
+//
+// A key, once used, keeps its meaning. Later versions may add keys, which older sessions simply
+// don't have, so every version can read the sessions of the versions before it. Sessions from a
+// newer version than this one are refused, rather than partly understood.
+//
+
+use game::{Game, Move};
+use grading::Difficulty;
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+use SudokuWeb;
+
+pub const VERSION: usize = 1;
+
+const HEADER: &str = "sudoku session";
+
+impl Game {
+    pub fn save(&self) -> String {
+        let web = self.web();
+        let mut text = format!("{} {}\n", HEADER, VERSION);
+        text += &format!("boxes {} {}\n", web.belts, web.curtains);
+        if let Some(difficulty) = self.difficulty {
+            text += &format!("difficulty {}\n", difficulty.name());
+        }
+        text += &format!("elapsed {:.3}\n", self.elapsed().as_secs_f64());
+        text += &format!("hints {}\n", self.hints);
+        text += &format!("cursor {} {}\n", self.cursor[0], self.cursor[1]);
+        text += &format!("pencil {}\n", if self.pencil { "yes" } else { "no" });
+        for [r, c, n] in web.prop_solution.iter() {
+            text += &format!("clue {} {} {}\n", r, c, n);
+        }
+        for [r, c, n] in self.entries.iter() {
+            text += &format!("entry {} {} {}\n", r, c, n);
+        }
+        for ([r, c], marks) in web.cells().into_iter().zip(self.marks.iter()) {
+            if *marks != 0 {
+                text += &format!("marks {} {} {}\n", r, c, marks);
+            }
+        }
+        for (key, moves) in [("undo", &self.undo), ("redo", &self.redo)] {
+            for m in moves.iter() {
+                text += &format!(
+                    "{} {} {} {} {} {} {}\n",
+                    key, m.cell[0], m.cell[1], m.before.0, m.before.1, m.after.0, m.after.1
+                );
+            }
+        }
+        text
+    }
+
+    // A game from a saved session, with its clock running again from where it was saved
+    pub fn load(text: &str) -> Result<Game, String> {
+        let mut lines = text
+            .lines()
+            .enumerate()
+            .map(|(i, line)| (i + 1, line.trim()))
+            .filter(|(_, line)| !line.is_empty());
+        let version = match lines.next() {
+            Some((_, line)) if line.starts_with(HEADER) => line[HEADER.len()..]
+                .trim()
+                .parse::<usize>()
+                .ok()
+                .filter(|version| *version > 0)
+                .ok_or_else(|| format!("bad session version: {}", line))?,
+            _ => return Err("not a sudoku session".to_string()),
+        };
+        if version > VERSION {
+            return Err(format!(
+                "session version {} is newer than this program reads ({})",
+                version, VERSION
+            ));
+        }
+
+        let mut boxes = None;
+        let mut difficulty = None;
+        let mut elapsed = Duration::ZERO;
+        let mut hints = 0;
+        let mut cursor = None;
+        let mut pencil = false;
+        let mut clues = vec![];
+        let mut entries = vec![];
+        let mut marks = vec![];
+        let mut moves = [vec![], vec![]];
+        for (number, line) in lines {
+            let mut words = line.split_whitespace();
+            let key = words.next().unwrap_or_default();
+            let words: Vec<&str> = words.collect();
+            let bad = || format!("line {}: bad {}: {}", number, key, line);
+            let numbers = |count: usize| -> Result<Vec<u64>, String> {
+                let numbers: Vec<u64> = words.iter().filter_map(|w| w.parse().ok()).collect();
+                if numbers.len() == count && words.len() == count {
+                    Ok(numbers)
+                } else {
+                    Err(bad())
+                }
+            };
+            let cell = |n: &[u64]| [n[0] as usize, n[1] as usize, n[2] as usize];
+            match key {
+                "boxes" => {
+                    let n = numbers(2)?;
+                    let fits = |n: u64| (1..=64).contains(&n);
+                    if !fits(n[0]) || !fits(n[1]) || !fits(n[0] * n[1]) {
+                        return Err(bad());
+                    }
+                    boxes = Some([n[0] as usize, n[1] as usize]);
+                }
+                "difficulty" => {
+                    difficulty = Some(
+                        *Difficulty::ALL
+                            .iter()
+                            .find(|d| words == [d.name()])
+                            .ok_or_else(bad)?,
+                    )
+                }
+                "elapsed" => {
+                    let secs = words
+                        .first()
+                        .and_then(|w| w.parse::<f64>().ok())
+                        .filter(|_| words.len() == 1)
+                        .ok_or_else(bad)?;
+                    elapsed = Duration::try_from_secs_f64(secs).map_err(|_| bad())?;
+                }
+                "hints" => hints = numbers(1)?[0] as usize,
+                "cursor" => {
+                    let n = numbers(2)?;
+                    cursor = Some([n[0] as usize, n[1] as usize]);
+                }
+                "pencil" => {
+                    pencil = match words[..] {
+                        ["yes"] => true,
+                        ["no"] => false,
+                        _ => return Err(bad()),
+                    }
+                }
+                "clue" => clues.push(cell(&numbers(3)?)),
+                "entry" => entries.push(cell(&numbers(3)?)),
+                "marks" => {
+                    let n = numbers(3)?;
+                    marks.push(([n[0] as usize, n[1] as usize], n[2]));
+                }
+                "undo" | "redo" => {
+                    let n = numbers(6)?;
+                    let m = Move {
+                        cell: [n[0] as usize, n[1] as usize],
+                        before: (n[2] as usize, n[3]),
+                        after: (n[4] as usize, n[5]),
+                    };
+                    moves[(key == "redo") as usize].push(m);
+                }
+                _ => return Err(format!("line {}: unknown key: {}", number, key)),
+            }
+        }
+
+        let [belts, curtains] = boxes.ok_or("the session has no boxes")?;
+        let mut web = SudokuWeb::new(belts, curtains);
+        let nums = web.symbols();
+        let symbol = |n: usize| n >= 1 && n <= nums;
+        let mask = |m: u64| nums == 64 || m >> nums == 0;
+        let cells = web.cells();
+        let on_board = |[r, c]: [usize; 2]| cells.contains(&[r, c]);
+        for [r, c, n] in clues.iter().chain(entries.iter()) {
+            if !on_board([*r, *c]) || !symbol(*n) {
+                return Err(format!("r{}c{} can't hold {}", r, c, n));
+            }
+        }
+        for m in moves[0].iter().chain(moves[1].iter()) {
+            let fits = |(n, marks): (usize, u64)| (n == 0 || symbol(n)) && mask(marks);
+            if !on_board(m.cell) || !fits(m.before) || !fits(m.after) {
+                return Err(format!("bad move at r{}c{}", m.cell[0], m.cell[1]));
+            }
+        }
+        web.prop_solution = clues;
+        let mut game = Game::new(web)?;
+
+        for [r, c, n] in entries {
+            if game.is_given(r, c) || game.value(r, c) != 0 {
+                return Err(format!("r{}c{} is entered twice, or over a clue", r, c));
+            }
+            game.entries.push([r, c, n]);
+        }
+        for ([r, c], m) in marks {
+            match cells.iter().position(|cell| *cell == [r, c]) {
+                Some(i) if mask(m) => game.marks[i] = m,
+                _ => return Err(format!("r{}c{} can't be marked {}", r, c, m)),
+            }
+        }
+        let [undo, redo] = moves;
+        game.undo = undo;
+        game.redo = redo;
+        game.difficulty = difficulty;
+        game.hints = hints;
+        game.pencil = pencil;
+        if let Some(cursor) = cursor.filter(|cursor| on_board(*cursor)) {
+            game.cursor = cursor;
+        }
+        game.set_elapsed(elapsed);
+        Ok(game)
+    }
+
+    pub fn save_file(&self, path: &Path) -> Result<(), String> {
+        fs::write(path, self.save()).map_err(|err| format!("{}: {}", path.display(), err))
+    }
+
+    pub fn load_file(path: &Path) -> Result<Game, String> {
+        let text =
+            fs::read_to_string(path).map_err(|err| format!("{}: {}", path.display(), err))?;
+        Game::load(&text).map_err(|err| format!("{}: {}", path.display(), err))
+    }
+}
@@ -0,0 +1,129 @@
+// Puzzles that must work under several geometries (or sets of variants) at once
+//
+// For example, a 6x6 puzzle with a unique solution whether its boxes are 2 rows by 3 columns or
+// 3 rows by 2 columns.
+//
+
+use rand::Rng;
+use {Knowing, SudokuWeb};
+
+// How a clue set fares across all of the webs it's meant for
+#[derive(Debug, PartialEq, Eq)]
+pub enum Shared {
+    // Exactly one solution in every web, and it's the same one
+    Unique,
+    // No solution in the web at this index
+    Impossible(usize),
+    // More than one solution in the web at this index
+    NotUnique(usize),
+    // A unique solution in the web at this index, but not the one the first web found
+    Differs(usize),
+    // The web at this index stopped at one of its `limits` before it could tell
+    LimitReached(usize),
+}
+
+// Solve `clues` in each of the webs, and report the first web that breaks uniqueness. Afterwards,
+// each web's `prop_solution` is `clues`, and its `solution` is whatever it found.
+pub fn check_shared(webs: &mut [SudokuWeb], clues: &[[usize; 3]]) -> Shared {
+    let mut first: Vec<[usize; 3]> = vec![];
+    for (w_i, sw) in webs.iter_mut().enumerate() {
+        sw.prop_solution = clues.to_vec();
+        if sw.solve(2, false, false).limit_reached() {
+            return Shared::LimitReached(w_i);
+        }
+        if sw.possible == Knowing::No {
+            return Shared::Impossible(w_i);
+        }
+        if sw.solution_count > 1 {
+            return Shared::NotUnique(w_i);
+        }
+        let mut solution = sw.solution.clone();
+        solution.sort();
+        if w_i == 0 {
+            first = solution;
+        } else if solution != first {
+            return Shared::Differs(w_i);
+        }
+    }
+    Shared::Unique
+}
+
+// Generate a clue set that has the same unique solution in every web, by removing clues from a
+// grid that satisfies all of them, like `SudokuWeb::random_puzzle` does. The webs must all have the
+// same symbols and the same cells.
+//
+// Returns `None` if no grid satisfies every web at once, or the first web's `limits` were
+// reached before one was found. If a web's limits are reached while removing clues, the clues so
+// far (which are unique) are returned as they are.
+//
+pub fn random_shared_puzzle(webs: &mut [SudokuWeb]) -> Option<Vec<[usize; 3]>> {
+    let grid = shared_grid(webs)?;
+
+    let mut clues = grid;
+    rand::thread_rng().shuffle(&mut clues);
+    for i in (0..clues.len()).rev() {
+        let gone = clues.remove(i);
+        match check_shared(webs, &clues) {
+            Shared::Unique => (),
+            Shared::LimitReached(_) => {
+                clues.push(gone);
+                break;
+            }
+            _ => clues.push(gone),
+        }
+    }
+    // Leave every web holding the final puzzle and its solution
+    check_shared(webs, &clues);
+    Some(clues)
+}
+
+// A random full grid that satisfies every web. Every column of every web but the first becomes a
+// secondary column of a copy of the first web, since a full grid that repeats nothing in a row,
+// column, block, etc. fills each of them exactly once. The copy borrows every web's rules, and
+// its cells allow only the symbols every web allows there.
+fn shared_grid(webs: &mut [SudokuWeb]) -> Option<Vec<[usize; 3]>> {
+    let (first, rest) = webs.split_first()?;
+    for sw in rest {
+        assert!(
+            sw.symbols() == first.symbols() && sw.cells() == first.cells(),
+            "shared puzzles need webs with the same symbols and cells"
+        );
+    }
+
+    let mut secondary = first.secondary.clone();
+    for sw in rest {
+        secondary.extend(sw.rows_of_columns());
+    }
+    // A cell may only hold what it may hold in every web
+    let allowed = if webs.iter().any(|sw| sw.allowed.is_some()) {
+        let mask_at = |r, c| webs.iter().fold(!0, |mask, sw| mask & sw.allowed_at(r, c));
+        first.cells().iter().map(|[r, c]| mask_at(*r, *c)).collect()
+    } else {
+        vec![]
+    };
+    let mut combined = SudokuWeb::assemble(
+        first.belts,
+        first.curtains,
+        first.grids.clone(),
+        secondary,
+        allowed,
+    );
+    combined.limits = first.limits.clone();
+    let counts: Vec<usize> = webs.iter().map(|sw| sw.rules.len()).collect();
+    for sw in webs.iter_mut() {
+        combined.rules.append(&mut sw.rules);
+    }
+
+    combined.solve(1, false, true);
+
+    let mut rules = combined.rules.drain(..);
+    for (sw, count) in webs.iter_mut().zip(counts) {
+        sw.rules.extend(rules.by_ref().take(count));
+    }
+
+    if combined.possible == Knowing::Yes {
+        Some(combined.solution)
+    } else {
+        None
+    }
+}
@@ -0,0 +1,188 @@
+// Puzzles and solutions as SVG, for print and the web
+//
+// Box borders and the edges of the board are thick, and the other cell edges thin, as decided by
+// `horiz_edge` and `vert_edge`; so every geometry, and every arrangement of grids, is drawn the
+// way its columns say. The givens (`prop_solution`) are bold and black, and the rest of a
+// solution is blue. Cages (see `Rule::cages`) are outlined in dashes just inside their cells,
+// with their totals in the corner of their first cell.
+//
+
+use SudokuWeb;
+
+// The size of a cell, the space around the board, and how far cage outlines are drawn inside
+// their cells
+const CELL: isize = 40;
+const MARGIN: isize = 10;
+const INSET: isize = 4;
+
+const STYLE: &str = "text{font-family:sans-serif;text-anchor:middle;dominant-baseline:central}\
+.given{font-size:24px;font-weight:bold;fill:#000}\
+.solved{font-size:24px;fill:#2a5db0}\
+.mark{font-size:10px;fill:#666}\
+.total{font-size:9px;text-anchor:start;dominant-baseline:hanging}";
+
+fn left(c: usize) -> isize {
+    MARGIN + (c as isize - 1) * CELL
+}
+
+fn top(r: usize) -> isize {
+    MARGIN + (r as isize - 1) * CELL
+}
+
+impl SudokuWeb {
+    // `sol`: what to fill in, besides the givens (which needn't be repeated)
+    // `marks`: pencil marks for the cells left empty, with a bit mask for each of `cells()` like
+    // `prop_candidates` (or none at all)
+    pub fn to_svg(&self, sol: &[[usize; 3]], marks: &[u64]) -> String {
+        let (height, width) = (self.height(), self.width());
+        let mut svg = format!(
+            concat!(
+                r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="{h}" "#,
+                r#"viewBox="0 0 {w} {h}">"#,
+                "\n<style>{}</style>\n",
+                r#"<rect width="{w}" height="{h}" fill="white"/>"#,
+                "\n"
+            ),
+            STYLE,
+            w = 2 * MARGIN + width as isize * CELL,
+            h = 2 * MARGIN + height as isize * CELL,
+        );
+
+        svg += &self.svg_cages();
+
+        // Every edge, thin ones first so thick ones cover their ends
+        let mut paths = [String::new(), String::new()];
+        for r in 1..=(height + 1) {
+            for c in 1..=width {
+                let weight = self.horiz_edge(r, c);
+                if weight > 0 {
+                    paths[weight - 1] += &format!("M{} {}h{}", left(c), top(r), CELL);
+                }
+            }
+        }
+        for r in 1..=height {
+            for c in 1..=(width + 1) {
+                let weight = self.vert_edge(r, c);
+                if weight > 0 {
+                    paths[weight - 1] += &format!("M{} {}v{}", left(c), top(r), CELL);
+                }
+            }
+        }
+        svg += &format!(
+            "<path d=\"{}\" stroke=\"#999\" stroke-width=\"1\" fill=\"none\"/>\n",
+            paths[0]
+        );
+        svg += &format!(
+            concat!(
+                "<path d=\"{}\" stroke=\"#000\" stroke-width=\"3\" ",
+                "stroke-linecap=\"square\" fill=\"none\"/>\n"
+            ),
+            paths[1]
+        );
+
+        let mut filled = vec![];
+        for (class, ids) in [("given", &self.prop_solution[..]), ("solved", sol)] {
+            for [r, c, n] in ids.iter() {
+                if filled.contains(&[*r, *c]) {
+                    continue;
+                }
+                filled.push([*r, *c]);
+                svg += &format!(
+                    "<text x=\"{}\" y=\"{}\" class=\"{}\">{}</text>\n",
+                    left(*c) + CELL / 2,
+                    top(*r) + CELL / 2,
+                    class,
+                    n
+                );
+            }
+        }
+
+        // Pencil marks in a little grid, as square as it can be
+        let nums = self.symbols();
+        let across = (1..=nums).find(|a| a * a >= nums).unwrap_or(1);
+        let down = nums.div_ceil(across);
+        let (step_x, step_y) = (CELL / across as isize, CELL / down as isize);
+        for ([r, c], mask) in self.cells().into_iter().zip(marks) {
+            if filled.contains(&[r, c]) {
+                continue;
+            }
+            for n in (1..=nums).filter(|n| mask & 1 << (n - 1) != 0) {
+                let (i, j) = ((n - 1) / across, (n - 1) % across);
+                svg += &format!(
+                    "<text x=\"{}\" y=\"{}\" class=\"mark\">{}</text>\n",
+                    left(c) + j as isize * step_x + step_x / 2,
+                    top(r) + i as isize * step_y + step_y / 2,
+                    n
+                );
+            }
+        }
+
+        svg += "</svg>\n";
+        svg
+    }
+
+    // A dashed outline just inside the edge of each cage, and its total
+    fn svg_cages(&self) -> String {
+        let mut svg = String::new();
+        for rule in self.rules.iter() {
+            for (cells, total) in rule.cages() {
+                let inside = |r: usize, c: usize, dr: isize, dc: isize| {
+                    let (r, c) = (r as isize + dr, c as isize + dc);
+                    r >= 1 && c >= 1 && cells.contains(&[r as usize, c as usize])
+                };
+                // How far inside its cell a side ends, given whether the cell next to it along
+                // the side (`along`) and the one diagonally past its end (`beyond`) are in the
+                // cage: at the inset for an outside corner, at the edge of the cell to run on
+                // into the next one, or past it for an inside corner.
+                let end = |along: bool, beyond: bool| match (along, beyond) {
+                    (false, _) => INSET,
+                    (true, false) => 0,
+                    (true, true) => -INSET,
+                };
+
+                let mut d = String::new();
+                for [r, c] in cells.iter().copied() {
+                    let (x, y) = (left(c), top(r));
+                    let at = |dr, dc| inside(r, c, dr, dc);
+                    if !at(-1, 0) {
+                        let from = x + end(at(0, -1), at(-1, -1));
+                        let to = x + CELL - end(at(0, 1), at(-1, 1));
+                        d += &format!("M{} {}H{}", from, y + INSET, to);
+                    }
+                    if !at(1, 0) {
+                        let from = x + end(at(0, -1), at(1, -1));
+                        let to = x + CELL - end(at(0, 1), at(1, 1));
+                        d += &format!("M{} {}H{}", from, y + CELL - INSET, to);
+                    }
+                    if !at(0, -1) {
+                        let from = y + end(at(-1, 0), at(-1, -1));
+                        let to = y + CELL - end(at(1, 0), at(1, -1));
+                        d += &format!("M{} {}V{}", x + INSET, from, to);
+                    }
+                    if !at(0, 1) {
+                        let from = y + end(at(-1, 0), at(-1, 1));
+                        let to = y + CELL - end(at(1, 0), at(1, 1));
+                        d += &format!("M{} {}V{}", x + CELL - INSET, from, to);
+                    }
+                }
+                svg += &format!(
+                    concat!(
+                        "<path d=\"{}\" stroke=\"#000\" stroke-width=\"1\" ",
+                        "stroke-dasharray=\"3 2\" fill=\"none\"/>\n"
+                    ),
+                    d
+                );
+
+                if let Some([r, c]) = cells.iter().min() {
+                    svg += &format!(
+                        "<text x=\"{}\" y=\"{}\" class=\"total\">{}</text>\n",
+                        left(*c) + INSET + 1,
+                        top(*r) + INSET + 1,
+                        total
+                    );
+                }
+            }
+        }
+        svg
+    }
+}
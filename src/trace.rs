@@ -0,0 +1,178 @@
+// What `dance` did while it searched, for finding out why some puzzles take so much longer than
+// others
+//
+// `solve_traced` counts the rows chosen (the nodes of the search tree) and the backtracks at every
+// depth, along with the columns chosen there and how many rows they had, and the time taken. It
+// can also write each step as a line of JSON:
+//
+//     {"event":"column","depth":0,"column":"r1c3","size":2}
+//     {"event":"choose","depth":0,"row":"r1c3=4"}
+//     {"event":"solution","depth":64,"count":1}
+//     {"event":"backtrack","depth":0,"row":"r1c3=4"}
+//     {"event":"done","nodes":65,"backtracks":65,"solutions":1,"micros":80,"outcome":"Finished"}
+//
+// Plain `solve` doesn't trace at all. Only `dance` is traced: when `solve` propagates instead
+// (see the `propagation` module), the statistics are all zero but the time.
+//
+
+use limits::Outcome;
+use std::io::Write;
+use std::time::{Duration, Instant};
+use SudokuWeb;
+
+// What happened at one depth of the search (the number of rows chosen above it)
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Level {
+    // Rows chosen at this depth
+    pub nodes: usize,
+    // Rows unchosen again at this depth
+    pub backtracks: usize,
+    // Columns chosen at this depth, and the sum of their sizes
+    pub columns: usize,
+    pub sizes: usize,
+    // Columns chosen at this depth with no rows left
+    pub dead_ends: usize,
+    // The column chosen here the last time the search got here, and its size
+    pub column: usize,
+    pub size: usize,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SearchStats {
+    pub levels: Vec<Level>,
+    pub solutions: usize,
+    pub elapsed: Duration,
+    pub outcome: Outcome,
+}
+
+impl SearchStats {
+    pub fn nodes(&self) -> usize {
+        self.levels.iter().map(|l| l.nodes).sum()
+    }
+
+    pub fn backtracks(&self) -> usize {
+        self.levels.iter().map(|l| l.backtracks).sum()
+    }
+}
+
+// Collects `SearchStats`, and writes the trace, for the hook `dance` searches with
+pub struct Tracer<'t> {
+    pub stats: SearchStats,
+    out: Option<&'t mut dyn Write>,
+    depth: usize,
+}
+
+impl<'t> Tracer<'t> {
+    pub fn new(out: Option<&'t mut dyn Write>) -> Self {
+        Tracer {
+            stats: SearchStats::default(),
+            out,
+            depth: 0,
+        }
+    }
+
+    fn level(&mut self) -> &mut Level {
+        if self.stats.levels.len() <= self.depth {
+            self.stats.levels.resize(self.depth + 1, Level::default());
+        }
+        &mut self.stats.levels[self.depth]
+    }
+
+    // Writes one line of the trace. If the writer fails, the trace stops there.
+    fn write(&mut self, line: String) {
+        if let Some(out) = self.out.as_mut() {
+            if writeln!(out, "{}", line).is_err() {
+                self.out = None;
+            }
+        }
+    }
+
+    pub fn column(&mut self, name: &str, column: usize, size: usize) {
+        let level = self.level();
+        level.columns += 1;
+        level.sizes += size;
+        level.column = column;
+        level.size = size;
+        if size == 0 {
+            level.dead_ends += 1;
+        }
+        if self.out.is_some() {
+            let line = format!(
+                r#"{{"event":"column","depth":{},"column":"{}","size":{}}}"#,
+                self.depth, name, size
+            );
+            self.write(line);
+        }
+    }
+
+    pub fn chosen(&mut self, [r, c, n]: [usize; 3]) {
+        self.level().nodes += 1;
+        if self.out.is_some() {
+            let line = format!(
+                r#"{{"event":"choose","depth":{},"row":"r{}c{}={}"}}"#,
+                self.depth, r, c, n
+            );
+            self.write(line);
+        }
+        self.depth += 1;
+    }
+
+    pub fn unchosen(&mut self, [r, c, n]: [usize; 3]) {
+        self.depth -= 1;
+        self.level().backtracks += 1;
+        if self.out.is_some() {
+            let line = format!(
+                r#"{{"event":"backtrack","depth":{},"row":"r{}c{}={}"}}"#,
+                self.depth, r, c, n
+            );
+            self.write(line);
+        }
+    }
+
+    pub fn found(&mut self) {
+        self.stats.solutions += 1;
+        if self.out.is_some() {
+            let line = format!(
+                r#"{{"event":"solution","depth":{},"count":{}}}"#,
+                self.depth, self.stats.solutions
+            );
+            self.write(line);
+        }
+    }
+
+    fn done(&mut self) {
+        if self.out.is_some() {
+            let line = format!(
+                concat!(
+                    r#"{{"event":"done","nodes":{},"backtracks":{},"solutions":{},"#,
+                    r#""micros":{},"outcome":"{:?}"}}"#
+                ),
+                self.stats.nodes(),
+                self.stats.backtracks(),
+                self.stats.solutions,
+                self.stats.elapsed.as_micros(),
+                self.stats.outcome
+            );
+            self.write(line);
+        }
+    }
+}
+
+impl SudokuWeb {
+    // Like `solve`, but returns what the search did, and writes a JSON line for each step of it
+    // to `out` (if there is one)
+    pub fn solve_traced(
+        &mut self,
+        seek: usize,
+        print: bool,
+        rand: bool,
+        out: Option<&mut dyn Write>,
+    ) -> SearchStats {
+        let start = Instant::now();
+        let mut tracer = Tracer::new(out);
+        tracer.stats.outcome = self.search(seek, print, rand, Some(&mut tracer));
+        tracer.stats.elapsed = start.elapsed();
+        tracer.done();
+        tracer.stats
+    }
+}
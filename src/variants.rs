@@ -0,0 +1,58 @@
+// Variant constraints that can be layered on top of a regular sudoku.
+//
+// Each variant only forbids certain `[r, c, n]` rows from appearing together, so they are all
+// expressed as secondary columns: one for every pair of rows that may not both be chosen.
+//
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Variant {
+    // No two equal digits a chess knight's move apart
+    AntiKnight,
+    // No two equal digits a chess king's move apart (diagonally touching, since orthogonally
+    // touching cells already share a row or column)
+    AntiKing,
+    // No two orthogonally adjacent cells with consecutive digits
+    NonConsecutive,
+}
+
+impl Variant {
+    // The offsets from a cell to the cells it may not repeat a digit in. Only offsets that point
+    // "forward" are listed, so each pair of cells is only visited once.
+    fn offsets(self) -> &'static [(isize, isize)] {
+        match self {
+            Variant::AntiKnight => &[(1, -2), (1, 2), (2, -1), (2, 1)],
+            Variant::AntiKing => &[(1, -1), (1, 1)],
+            Variant::NonConsecutive => &[(0, 1), (1, 0)],
+        }
+    }
+
+    // The pairs of digits that may not appear together in two such cells
+    fn clashes(self, nums: usize) -> Vec<(usize, usize)> {
+        match self {
+            Variant::AntiKnight | Variant::AntiKing => (1..=nums).map(|n| (n, n)).collect(),
+            Variant::NonConsecutive => (1..nums)
+                .flat_map(|n| vec![(n, n + 1), (n + 1, n)])
+                .collect(),
+        }
+    }
+
+    // The secondary columns for this variant on a grid with `nums` rows, columns, and symbols
+    pub fn secondary_columns(self, nums: usize) -> Vec<Vec<[usize; 3]>> {
+        let mut columns = vec![];
+        for r in 1..=nums {
+            for c in 1..=nums {
+                for (dr, dc) in self.offsets() {
+                    let (r2, c2) = (r as isize + dr, c as isize + dc);
+                    if r2 < 1 || c2 < 1 || r2 > nums as isize || c2 > nums as isize {
+                        continue;
+                    }
+                    let (r2, c2) = (r2 as usize, c2 as usize);
+                    for (n, n2) in self.clashes(nums) {
+                        columns.push(vec![[r, c, n], [r2, c2, n2]]);
+                    }
+                }
+            }
+        }
+        columns
+    }
+}
@@ -0,0 +1,202 @@
+// The whole logical solving path of a puzzle, step by step, for tutorials
+//
+// A `Walkthrough` is what `Logic` does to the puzzle in `prop_solution`, from the clues to the
+// solution: each step, with the board and the candidates left after it. It can be written out as
+// JSON, for other programs:
+//
+//     {
+//       "clues": [[1,4,8],...],
+//       "solution": [[1,1,2],...],
+//       "steps": [
+//         {"technique":"Hidden single","cells":[[1,3]],"placement":[1,3,3],"eliminations":[],
+//          "explanation":"...","board":[[0,0,3,...],...],"candidates":[[[2,7],[],...],...]},
+//         ...
+//       ]
+//     }
+//
+// where `board` has a row of symbols for each row of the board (0 for empty cells), and
+// `candidates` the candidates left in each cell; or as a Markdown walkthrough, with the board
+// drawn after every step (with its pencil marks, after steps that eliminate candidates).
+//
+
+use logic::{cell_name, Logic, Step};
+use {Knowing, SudokuWeb};
+
+// One step, and where it left the puzzle
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Stage {
+    pub step: Step,
+    pub board: Vec<[usize; 3]>,
+    // A bit mask for each of `cells()`, like `prop_candidates`
+    pub candidates: Vec<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Walkthrough {
+    pub clues: Vec<[usize; 3]>,
+    pub solution: Vec<[usize; 3]>,
+    pub stages: Vec<Stage>,
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::new();
+    for ch in text.chars() {
+        match ch {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            ch if (ch as u32) < 0x20 => escaped.push_str(&format!("\\u{:04x}", ch as u32)),
+            ch => escaped.push(ch),
+        }
+    }
+    escaped
+}
+
+fn ids_json(ids: &[[usize; 3]]) -> String {
+    let ids: Vec<String> = ids
+        .iter()
+        .map(|[r, c, n]| format!("[{},{},{}]", r, c, n))
+        .collect();
+    format!("[{}]", ids.join(","))
+}
+
+impl SudokuWeb {
+    // Solve the puzzle in `prop_solution` step by step. Fails unless it has a unique solution.
+    pub fn walkthrough(&mut self) -> Result<Walkthrough, String> {
+        if self.solve(2, false, false).limit_reached() || self.uniq != Knowing::Yes {
+            return Err("the puzzle doesn't have a unique solution".to_string());
+        }
+        let clues = self.prop_solution.clone();
+        let solution = self.solution.clone();
+        let mut stages = vec![];
+        let mut logic = Logic::new(self, &clues, &solution);
+        while let Some(step) = logic.next_step() {
+            logic.apply(&step);
+            stages.push(Stage {
+                step,
+                board: logic.board(),
+                candidates: logic.marks(),
+            });
+        }
+        Ok(Walkthrough {
+            clues,
+            solution,
+            stages,
+        })
+    }
+
+    // `board` as rows of symbols, and `candidates` as rows of lists of them
+    fn board_json(&self, board: &[[usize; 3]], candidates: &[u64]) -> (String, String) {
+        let (height, width) = (self.height(), self.width());
+        let mut values = vec![vec![0; width]; height];
+        for [r, c, n] in board.iter() {
+            values[r - 1][c - 1] = *n;
+        }
+        let mut marks = vec![vec![0; width]; height];
+        for ([r, c], mask) in self.cells().into_iter().zip(candidates) {
+            marks[r - 1][c - 1] = *mask;
+        }
+
+        let rows: Vec<String> = values
+            .iter()
+            .map(|row| {
+                let row: Vec<String> = row.iter().map(|n| n.to_string()).collect();
+                format!("[{}]", row.join(","))
+            })
+            .collect();
+        let mark_rows: Vec<String> = marks
+            .iter()
+            .map(|row| {
+                let row: Vec<String> = row
+                    .iter()
+                    .map(|mask| {
+                        let symbols: Vec<String> = (1..=self.symbols())
+                            .filter(|n| mask & 1 << (n - 1) != 0)
+                            .map(|n| n.to_string())
+                            .collect();
+                        format!("[{}]", symbols.join(","))
+                    })
+                    .collect();
+                format!("[{}]", row.join(","))
+            })
+            .collect();
+        (
+            format!("[{}]", rows.join(",")),
+            format!("[{}]", mark_rows.join(",")),
+        )
+    }
+
+    pub fn walkthrough_json(&self, walkthrough: &Walkthrough) -> String {
+        let mut json = "{\n".to_string();
+        json += &format!("  \"clues\": {},\n", ids_json(&walkthrough.clues));
+        json += &format!("  \"solution\": {},\n", ids_json(&walkthrough.solution));
+        json += "  \"steps\": [";
+        for (i, stage) in walkthrough.stages.iter().enumerate() {
+            let step = &stage.step;
+            let cells: Vec<String> = step
+                .cells
+                .iter()
+                .map(|[r, c]| format!("[{},{}]", r, c))
+                .collect();
+            let placement = match step.placement {
+                Some([r, c, n]) => format!("[{},{},{}]", r, c, n),
+                None => "null".to_string(),
+            };
+            let (board, candidates) = self.board_json(&stage.board, &stage.candidates);
+            json += if i == 0 { "\n" } else { ",\n" };
+            json += &format!(
+                concat!(
+                    "    {{\"technique\":\"{}\",\"cells\":[{}],\"placement\":{},",
+                    "\"eliminations\":{},\"explanation\":\"{}\",\"board\":{},\"candidates\":{}}}"
+                ),
+                escape(step.technique.name()),
+                cells.join(","),
+                placement,
+                ids_json(&step.eliminations),
+                escape(&step.explanation),
+                board,
+                candidates
+            );
+        }
+        json += "\n  ]\n}\n";
+        json
+    }
+
+    pub fn walkthrough_markdown(&self, walkthrough: &Walkthrough) -> String {
+        let mut md = "# Walkthrough\n\n".to_string();
+        md += &format!(
+            "The puzzle has {} clues, and takes {} steps:\n\n",
+            walkthrough.clues.len(),
+            walkthrough.stages.len()
+        );
+
+        // How often each technique is used, in the order they're first used
+        let mut counts: Vec<(&str, usize)> = vec![];
+        for stage in walkthrough.stages.iter() {
+            let name = stage.step.technique.name();
+            match counts.iter_mut().find(|(n, _)| *n == name) {
+                Some((_, count)) => *count += 1,
+                None => counts.push((name, 1)),
+            }
+        }
+        md += "| Technique | Steps |\n|---|---|\n";
+        for (name, count) in counts {
+            md += &format!("| {} | {} |\n", name, count);
+        }
+        md += &format!("\n```\n{}```\n", self.grid(&walkthrough.clues));
+
+        for (i, stage) in walkthrough.stages.iter().enumerate() {
+            let step = &stage.step;
+            md += &format!("\n## Step {}: {}\n\n", i + 1, step.technique.name());
+            md += &format!("{}.\n\n", step.explanation);
+            if let Some([r, c, n]) = step.placement {
+                md += &format!("Place {} in {}.\n", n, cell_name([r, c]));
+                md += &format!("\n```\n{}```\n", self.grid(&stage.board));
+            } else {
+                let grid = self.grid(&stage.board).marks(&stage.candidates);
+                md += &format!("```\n{}```\n", grid);
+            }
+        }
+        md
+    }
+}